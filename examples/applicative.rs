@@ -0,0 +1,41 @@
+//! examples/applicative.rs
+//!
+//! independent steps combined with the applicative macro `ado`
+
+use monadic::{ado, hkt::{fmap, zip_with}, 
+              reader::{Reader, ask}, 
+              state::{State, get}, 
+              writer::{Writer, tell_str}};
+
+type Env = i32;
+
+fn main() {
+    let opt = ado!{
+        x <- Some(1);
+        y <- "2".parse::<i32>().ok();
+        pure x + y
+    };
+    
+    let rdr: Reader<'_, Env, _> = ado!{
+        x <- ask();
+        y <- fmap( ask(), |e: Env| e * 10);
+        pure (x, y)
+    };
+    
+    let st: State<'_, i32, _> = ado!{
+        x <- get();
+        y <- State::pure( "const");
+        pure (x, y)
+    };
+    
+    let wr: Writer<_> = ado!{
+        _ <- tell_str( "log1 ");
+        y <- Writer::pure( 5);
+        _ <- tell_str( "log2");
+        pure y
+    };
+    
+    let zipped = zip_with( vec![1, 2, 3], "abc".chars(), |n, c| format!("{}{}", c, n)).collect::<Vec<_>>();
+
+    println!("result: {:?}", (opt, rdr.initial_env( 3), st.initial_state( 7), wr.unwrap_pair(), zipped));
+}
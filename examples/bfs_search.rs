@@ -0,0 +1,25 @@
+//! examples/bfs_search.rs
+//!
+//! shortest solutions first with the breadth first monad of module bfs
+
+use monadic::{bfsdo, bfs::{Bfs, choose}};
+
+// the sequences of moves of a knight-like jumper from 0 to the target, one level per move
+fn jumps(pos: i32, target: i32) -> Bfs<'static, Vec<i32>> {
+    if pos == target { return Bfs::pure( vec![pos]) }
+    bfsdo!{
+        step <- choose( vec![5, -3]);
+        let next = pos + step;
+        guard next.abs() <= 20;
+        path <- jumps( next, target);
+        pure [vec![pos], path].concat()
+    }
+}
+
+fn main() {
+    // the shortest paths, and the number of paths per length within 11 moves
+    let shortest = jumps( 0, 7).shortest();
+    let counts = jumps( 0, 7).to_depth( 11).levels().map( |level| level.len()).collect::<Vec<_>>();
+    
+    println!("result: {:?}", (shortest, counts));
+}
@@ -0,0 +1,27 @@
+// example console io with the Result monad macro resdo
+//
+// a parse failure is returned as the Err value instead of an empty iterator
+
+use monadic::{resdo, monad::Monad, 
+                     mio::{read_line, print_str, stdout_flush}};
+
+fn main() {
+    let res: Result<_, String> = resdo!{
+    
+                x <- pure 1;
+                let y = x + 1;
+                
+                _ <- print_str("enter a positive integer i32>").map_err( |e| e.to_string());
+                _ <- stdout_flush().map_err( |e| e.to_string());
+                
+                li1 <- read_line().map_err( |e| e.to_string());
+                z <- li1.trim().parse::<i32>().map_err( |e| e.to_string()) ;
+                
+                guard z > 0, format!("not positive: {}", z);
+                
+                pure (y, z, li1.clone())
+                
+              };
+
+    println!("result: {:?}", res);              
+}
@@ -0,0 +1,20 @@
+//! examples/control_flow.rs
+//!
+//! generic monadic control combinators of module control
+
+use monadic::control::{filter_m, fold_m, unless};
+use monadic::state::{State, get, put};
+use monadic::writer::{Writer, tell_str};
+
+fn main() {
+    // the powerset through the list monad
+    let powerset = filter_m( |_| vec![true, false], vec![1, 2, 3]);
+    
+    // sum counting the steps in the state
+    let sum = fold_m( |acc, x| get().bind( move |n: i32| put( n + 1).bind( move |_| State::pure( acc + x))), 
+                      0, vec![5, 6, 7]);
+                      
+    let log: Writer<(), String> = unless( powerset.is_empty(), tell_str( "not empty"));
+    
+    println!("result: {:?}", (powerset, sum.initial_state( 0), log.unwrap_pair()));
+}
@@ -0,0 +1,26 @@
+//! examples/fair_search.rs
+//!
+//! fair enumeration of infinite generators with the macro fairdo of module logic
+
+use monadic::{fairdo, monad::Monad, logic::MonadLogic};
+
+fn main() {
+    // with mdo the first generator would never leave x = 1
+    let pairs = fairdo!{
+        x <- 1..;
+        y <- 1..;
+        pure (x, y)
+    }.take(6).collect::<Vec<(u32, u32)>>();
+    
+    // fair disjunction of two infinite sources
+    let mixed = (1..).interleave( (1..).map( |x: i32| -x)).take(6).collect::<Vec<_>>();
+    
+    // soft cut: the numbers without a small divisor
+    let primes = fairdo!{
+        n <- 2u32..;
+        p <- (2..n).filter( move |d| n % d == 0).once().ifte( |_| None, Some(n));
+        pure p
+    }.take(6).collect::<Vec<_>>();
+    
+    println!("result: {:?}", (pairs, mixed, primes));
+}
@@ -0,0 +1,27 @@
+//! examples/fallback.rs
+//!
+//! fallback chains with the choice `<|>` on the right of `<-`
+
+use monadic::{optdo, monad::{Monad, msum}, control::optional};
+use std::collections::HashMap;
+
+fn from_disk(key: &str) -> Option<u32> {
+    println!("reading {} from disk", key);
+    if key.starts_with( 'd') {Some(key.len() as u32)} else {None}
+}
+
+fn main() {
+    let cache: HashMap<&str, u32> = vec![("cached", 1)].into_iter().collect();
+    
+    // try the cache, then the disk, then a default; the disk is not read on a cache hit
+    let size = |key| optdo!{
+        n <- (cache.get( key).cloned() <|> from_disk( key) <|> pure 0);
+        pure n * 100
+    };
+    
+    let sizes = (size( "cached"), size( "disk"), size( "none"));
+    let first = msum( vec![None, Some("b"), Some("c")]);
+    let maybe = optional( "12x".parse::<u32>());
+    
+    println!("result: {:?}", (sizes, first, maybe));
+}
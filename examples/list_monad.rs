@@ -0,0 +1,30 @@
+//! examples/list_monad.rs
+//!
+//! lazy comprehensions as first-class values with ListM of module list
+
+use monadic::{mdo, monad::{Bind, Monad}, list::ListM};
+
+struct Solver<'a> {
+    solutions: ListM<'a, (u32, u32)>,
+}
+
+// the pairs of divisors of n, a lazy comprehension returned from a function
+fn divisor_pairs<'a>(n: u32) -> ListM<'a, (u32, u32)> {
+    mdo!{
+        x <- ListM::new( move || 1..=n);
+        guard n % x == 0;
+        let y = n / x;
+        guard x <= y;
+        pure (x, y)
+    }
+}
+
+fn main() {
+    let solver = Solver { solutions: divisor_pairs( 36)};
+    
+    // cloning shares the comprehension, each traversal runs it again
+    let first = solver.solutions.clone().into_iter().next();
+    let all = solver.solutions.to_vec();
+    
+    println!("result: {:?}", (first, all));
+}
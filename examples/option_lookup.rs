@@ -0,0 +1,28 @@
+//! examples/option_lookup.rs
+//!
+//! optional-lookup chains with the Option monad macro optdo
+//! that returns an Option instead of a FlatMap iterator
+
+use monadic::{optdo, monad::{Monad, MZero}};
+use std::collections::HashMap;
+
+fn managers_office<'a>( name: &str, 
+                        managers: &HashMap<&str, &'a str>, 
+                        offices: &HashMap<&str, u32>) -> Option<(&'a str, u32)> {
+    optdo!{
+        &manager <- managers.get( name);
+        &office <- offices.get( manager);
+        guard office > 100;
+        pure (manager, office)
+    }
+}
+
+fn main() {
+    let managers: HashMap<&str, &str> = vec![("ann", "bob"), ("bob", "cid")].into_iter().collect();
+    let offices: HashMap<&str, u32> = vec![("cid", 101)].into_iter().collect();
+    
+    let res = (managers_office( "bob", &managers, &offices), 
+               managers_office( "ann", &managers, &offices));
+    
+    println!("result: {:?}", res);
+}
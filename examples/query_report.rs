@@ -0,0 +1,40 @@
+//! examples/query_report.rs
+//!
+//! SQL like comprehension stages with the macro qdo of module query
+
+use monadic::qdo;
+
+fn main() {
+    let orders = [("ann", "tea", 3), ("bob", "coffee", 2), ("ann", "coffee", 5), 
+                      ("cid", "tea", 1), ("bob", "tea", 4), ("ann", "tea", 2)];
+    
+    // customers by total quantity, top two
+    let report = qdo!{
+        order <- orders.iter();
+        let (customer, product, qty) = *order;
+        then group by customer => who;
+        let total: i32 = qty.iter().sum();
+        then sort by std::cmp::Reverse(total);
+        then take 2;
+        pure (who, total, product)
+    }.collect::<Vec<_>>();
+    
+    // the distinct products in order of appearance, as distinct compares all the variables in scope
+    let products = qdo!{
+        product <- orders.iter().map( |order| order.1);
+        then distinct;
+        pure product
+    }.collect::<Vec<_>>();
+    
+    // orders joined with the customer cities through a hash join
+    let cities = [("ann", "Oslo"), ("bob", "Rome"), ("cid", "Oslo")];
+    let tea_by_city = qdo!{
+        order <- orders.iter();
+        join cust <- cities.iter() on order.0 == cust.0;
+        guard order.1 == "tea";
+        then group by cust.1 => city;
+        pure (city, order.len())
+    }.collect::<Vec<_>>();
+    
+    println!("result: {:?}", (report, products, tea_by_city));
+}
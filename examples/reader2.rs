@@ -0,0 +1,33 @@
+//! examples/reader2.rs
+//!
+//! `asks` reads a field of the environment, `reader` lifts a function, 
+//! and `with_reader` runs a bloc in an environment built from another type
+
+use monadic::{rdrdo, reader::{Reader, asks, reader}};
+
+#[derive(Clone)]
+struct Config { host: String, port: u16, retries: u32 }
+
+fn address<'a>() -> Reader<'a, Config, String> {
+  rdrdo!{
+       host <- asks( |c: &Config| c.host.clone());
+       port <- asks( |c: &Config| c.port);
+       pure format!( "{}:{}", host, port)
+    }
+}
+
+fn main() {
+
+  let bloc = rdrdo!{
+       addr <- address();
+       retries <- reader( |c: Config| c.retries);
+       pure format!( "connect to {} ({} retries)", addr, retries)
+    };
+    
+  // the configuration from the command line arguments
+  let from_args = bloc.with_reader( |args: Vec<&str>| Config { host: args[0].to_string(), port: args[1].parse().unwrap(), retries: 3});
+  
+  let res = from_args.map_reader( |s| s.to_uppercase()).run_reader( vec!["localhost", "8080"]);
+
+  println!("result: {}", res);  
+}
@@ -0,0 +1,33 @@
+//! examples/reader_shared.rs
+//!
+//! an environment that is not Clone, passed by reference through the binds
+
+use monadic::{shared_rdrdo, reader::SharedReader};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// a resource that can't be cloned
+struct Db { rows: HashMap<u32, &'static str>, queries: RefCell<u32> }
+
+fn query<'a>(id: u32) -> SharedReader<'a, Db, Option<&'static str>> {
+  SharedReader::asks( move |db: &Db| {
+      *db.queries.borrow_mut() += 1;
+      db.rows.get( &id).copied()
+  })
+}
+
+fn main() {
+
+  let bloc = shared_rdrdo!{
+       a <- query( 1);
+       b <- query( 2);
+       db <- SharedReader::ask();
+       pure (a, b, *db.queries.borrow())
+    };
+    
+  let db = Rc::new( Db { rows: vec![(1, "one")].into_iter().collect(), queries: RefCell::new( 0)});
+  
+  println!("result: {:?}", bloc.run_shared( &db));  
+  println!("result: {:?}", bloc.run_shared( &db));  
+}
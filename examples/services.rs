@@ -0,0 +1,40 @@
+//! examples/services.rs
+//!
+//! services injected by type, with one of them swapped for a bloc
+
+use monadic::{rdrdo, reader::Reader, services::{Services, ask_of, try_ask_of, local_of}};
+
+trait Clock { fn now(&self) -> u64; }
+
+struct SystemClock;
+impl Clock for SystemClock { fn now(&self) -> u64 { 1_600_000_000 } }
+
+struct FixedClock(u64);
+impl Clock for FixedClock { fn now(&self) -> u64 { self.0 } }
+
+struct Prefix(String);
+
+// services of trait objects are registered as boxes
+type DynClock = Box<dyn Clock>;
+
+fn stamp<'a>(msg: &'a str) -> Reader<'a, Services, String> {
+  rdrdo!{
+       clock <- ask_of::<DynClock>();
+       prefix <- ask_of::<Prefix>();
+       pure format!( "{}[{}] {}", prefix.0, clock.now(), msg)
+    }
+}
+
+fn main() {
+
+  let env = Services::new().with::<DynClock>( Box::new( SystemClock)).with( Prefix( "> ".to_string()));
+  
+  let live = stamp( "live").initial_env( env.clone());
+  
+  // the same bloc with a fixed clock
+  let test = local_of::<DynClock, _>( Box::new( FixedClock( 0)), stamp( "test")).initial_env( env.clone());
+  
+  let missing = try_ask_of::<u32>().initial_env( env).err().map( |err| err.to_string());
+
+  println!("result: {:?}", (live, test, missing));  
+}
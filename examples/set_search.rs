@@ -0,0 +1,31 @@
+//! examples/set_search.rs
+//!
+//! reachability closure with the set monad, expanding each reached state once per step
+
+use monadic::{setdo, monad::{Monad, MPlus}};
+use std::collections::BTreeSet;
+
+// the moves of a counter: double it or add 3, up to 20
+fn moves(n: u32) -> Vec<u32> {
+    vec![n * 2, n + 3].into_iter().filter( |&m| m <= 20).collect()
+}
+
+fn main() {
+    let mut reached = BTreeSet::pure( 1);
+    let mut frontier = reached.clone();
+    let mut steps = 0;
+    
+    while !frontier.is_empty() {
+        let known = &reached;
+        frontier = setdo!{
+            x <- frontier;
+            y <- moves( x);
+            guard !known.contains( &y);
+            pure y
+        };
+        steps += 1;
+        reached.mplus( &mut frontier.clone());
+    }
+    
+    println!("{} steps, reached: {:?}", steps, reached);
+}
@@ -0,0 +1,33 @@
+//! examples/state2.rs
+//!
+//! `modify` and `gets` update and read the state without cloning it,
+//! and `eval_state` and `exec_state` keep one half of the result
+
+use monadic::{stdo, state::{State, modify, gets}};
+use std::collections::HashMap;
+
+struct Stats { words: HashMap<String, u32>, total: u32 }
+
+fn count<'a>(word: &'a str) -> State<'a, Stats, u32> {
+  stdo!{
+       _ <- modify( move |mut st: Stats| {
+                *st.words.entry( word.to_string()).or_insert( 0) += 1;
+                st.total += 1;
+                st
+            });
+       gets( move |st: &Stats| st.words[word])
+    }
+}
+
+fn main() {
+
+  let text = "the cat and the hat";
+  let bloc = text.split_whitespace().fold( State::pure( 0), |acc, w| acc.bind( move |_| count( w)));
+  
+  let init = || Stats { words: HashMap::new(), total: 0};
+  
+  let last_count = bloc.clone().eval_state( init());
+  let stats = bloc.exec_state( init());
+
+  println!("last word count: {}, total: {}, 'the': {}", last_count, stats.total, stats.words["the"]);  
+}
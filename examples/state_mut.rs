@@ -0,0 +1,41 @@
+//! examples/state_mut.rs
+//!
+//! union-find on a state updated in place, without clones
+
+use monadic::{stmdo, state_mut::{StateMut, modify, gets}};
+
+struct Sets { parent: Vec<usize> }
+
+fn find(parent: &[usize], mut x: usize) -> usize {
+    while parent[x] != x { x = parent[x]; }
+    x
+}
+
+fn union<'a>(a: usize, b: usize) -> StateMut<'a, Sets, ()> {
+  modify( move |st: &mut Sets| {
+      let (ra, rb) = (find( &st.parent, a), find( &st.parent, b));
+      st.parent[ra] = rb;
+  })
+}
+
+fn count_sets<'a>() -> StateMut<'a, Sets, usize> {
+  gets( |st: &Sets| (0..st.parent.len()).filter( |&x| find( &st.parent, x) == x).count())
+}
+
+fn main() {
+
+  let mut sets = Sets { parent: (0..100_000).collect()};
+  
+  let bloc = stmdo!{
+       _ <- union( 1, 2);
+       _ <- union( 3, 4);
+       _ <- union( 2, 4);
+       n <- count_sets();
+       same <- gets( |st: &Sets| find( &st.parent, 1) == find( &st.parent, 3));
+       pure (n, same)
+    };
+    
+  let res = bloc.run( &mut sets);
+
+  println!("result: {:?}", res);  
+}
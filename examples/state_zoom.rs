@@ -0,0 +1,40 @@
+//! examples/state_zoom.rs
+//!
+//! subsystem blocks on one field of the application state, with `zoom`
+
+use monadic::{stdo, zoom_field, state::{State, zoom, modify, gets}};
+use std::collections::HashMap;
+
+#[derive(Default)]
+struct Inventory { items: HashMap<String, u32> }
+
+struct AppState { inventory: Inventory, clock: u64 }
+
+// the inventory subsystem knows nothing of the application state
+fn add<'a>(item: &'a str, n: u32) -> State<'a, Inventory, u32> {
+  stdo!{
+       _ <- modify( move |mut inv: Inventory| { *inv.items.entry( item.to_string()).or_insert( 0) += n; inv });
+       gets( move |inv: &Inventory| inv.items[item])
+    }
+}
+
+fn tick<'a>() -> State<'a, u64, u64> {
+  stdo!{
+       _ <- modify( |t: u64| t + 1);
+       gets( |t: &u64| *t)
+    }
+}
+
+fn main() {
+
+  let bloc = stdo!{
+       _ <- zoom_field!( AppState, inventory, add( "apple", 3));
+       apples <- zoom_field!( AppState, inventory, add( "apple", 2));
+       time <- zoom( |s: &mut AppState| s.clock, |s: &mut AppState, t| s.clock = t, tick());
+       pure (apples, time)
+    };
+    
+  let (res, st) = bloc.initial_state( AppState { inventory: Inventory::default(), clock: 10});
+
+  println!("result: {:?}, items: {:?}, clock: {}", res, st.inventory.items, st.clock);  
+}
@@ -0,0 +1,41 @@
+//! examples/static_state.rs
+//!
+//! a statically dispatched State block, a composition of closures without boxes,
+//! converted to the boxed `State` to be used in a recursive block
+
+use monadic::{stdo, static_stdo, state::{State, StateFn}};
+
+type St = (u32, u32);  // fibonacci pair
+
+fn main() {
+
+  let step = static_stdo!{ 
+  
+       st <- StateFn::get::<St>();
+       let (a, b) = (st.0, st.1);
+       _ <- StateFn::put( (b, a + b));
+       
+       pure a
+    };
+    
+  // the block size is the one of its captures, here none
+  println!("size: {}", std::mem::size_of_val( &step));
+  
+  let firsts: Vec<u32> = (0..10).scan( (0, 1), |st, _| {
+                                    let (x, st1) = step.initial_state( *st);
+                                    *st = st1;
+                                    Some(x)
+                                 }).collect();
+  println!("firsts: {:?}", firsts);
+  
+  fn nth<'a>(n: u32, step: State<'a, St, u32>) -> State<'a, St, u32> {
+      stdo!{
+         x <- step.clone();
+         if n == 0 {State::pure( x)} else {nth( n - 1, step.clone())}
+      }
+  }
+  
+  let res = nth( 30, step.boxed()).initial_state( (0, 1));
+  
+  println!("result: {:?}", res);
+}
@@ -0,0 +1,28 @@
+//! examples/stream_memo.rs
+//!
+//! a comprehension over a one-shot source, evaluated at most once with Stream of module stream
+
+use monadic::{mdo, monad::{Bind, Monad}, stream::Stream};
+use std::cell::Cell;
+
+fn main() {
+    let counter = Cell::new( 0);
+    let calls = &counter;
+    
+    // a one-shot iterator, as the lines read from the console
+    let lines = vec!["3", "x", "4"].into_iter();
+    
+    let numbers = mdo!{
+        line <- Stream::new( lines);
+        let _ = calls.set( calls.get() + 1);  // a costly step
+        n <- line.parse::<i32>();
+        pure n * 10
+    };
+    
+    // shared between consumers, forced once
+    let shared = numbers.clone();
+    let total: i32 = numbers.iter().sum();
+    let all = shared.into_iter().collect::<Vec<_>>();
+    
+    println!("result: {:?}", (total, all, counter.get()));
+}
@@ -0,0 +1,25 @@
+//! examples/tail_rec.rs
+//!
+//! a million step simulation with `tail_rec_m`, in constant stack
+
+use monadic::{stdo, monad::Step, state::{self, State, get, put}};
+
+type St = (u64, i64);  // (random seed, position)
+
+fn main() {
+
+  // a random walk, moving one step back or forward at each tick
+  let walk = state::tail_rec_m( 0u32, |tick| stdo!{
+  
+       st <- get::<St>();
+       let seed1 = st.0.wrapping_mul( 6364136223846793005).wrapping_add( 1442695040888963407);
+       let pos1 = if seed1 >> 63 == 0 {st.1 - 1} else {st.1 + 1};
+       _ <- put( (seed1, pos1));
+       
+       pure if tick < 1_000_000 {Step::Loop( tick + 1)} else {Step::Done( tick)}
+    });
+    
+  let res: (u32, St) = walk.initial_state( (42, 0));
+
+  println!("ticks: {}, final position: {}", res.0, (res.1).1);
+}
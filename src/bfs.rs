@@ -0,0 +1,327 @@
+//! A breadth first nondeterminism monad, with the macro "bfsdo"
+//!
+//! `Bfs<'a, T>` is a lazy stream of levels, the results at each search depth.
+//! Bind starts the levels of each continuation at the depth of its argument,
+//! so the results come out shortest first, instead of the depth first order of the list monad behind `mdo`.
+//!
+//! `choose` offers alternatives one level deeper (a search step), `wrap` delays a computation one level,
+//! and `to_depth` bounds the search depth, for an iterative deepening over `levels`.
+//!
+//! The macro `bfsdo` has the syntax of `mdo`, so switching search strategy is changing the macro and the source type.
+//!
+//! ```
+//! # #[macro_use] extern crate monadic;
+//! use monadic::bfs::{Bfs, choose};
+//!
+//! # fn main() {
+//! // the shortest sequences of steps +1 or *2 from 1 to 10
+//! fn steps(n: u32) -> Bfs<'static, Vec<char>> {
+//!     if n == 10 { return Bfs::pure( Vec::new()) }
+//!     if n > 10 { return Bfs::mzero() }
+//!     bfsdo!{
+//!         op <- choose( vec!['+', '*']);
+//!         ops <- steps( if op == '+' { n + 1 } else { n * 2 });
+//!         pure [vec![op], ops].concat()
+//!     }
+//! }
+//!
+//! assert_eq!( steps( 1).shortest(), vec![vec!['+', '*', '+', '*'], vec!['*', '*', '+', '*']]);
+//! # }
+//! ```
+
+use crate::monad::{Monad, MZero, MPlus};
+use crate::hkt::{Kind, Kinded, Functor, Applicative, MonadK};
+use std::iter::{self, Flatten, FromIterator};
+use std::mem;
+use std::rc::Rc;
+use std::cell::RefCell;
+
+pub struct Bfs<'a, T> {
+  levels: Box<dyn 'a + Iterator<Item = Vec<T>>>,
+}
+
+impl<'a, T: 'a> Bfs<'a, T> {
+
+  /// a single result at depth 0
+  pub fn pure(x: T) -> Self {
+     Bfs { levels: Box::new( iter::once( vec![x]))}
+  }
+
+  /// no results
+  pub fn mzero() -> Self {
+     Bfs { levels: Box::new( iter::empty())}
+  }
+
+  /// from a stream of levels
+  pub fn from_levels<L>(levels: L) -> Self
+    where L: 'a + IntoIterator<Item = Vec<T>>,
+  {
+     Bfs { levels: Box::new( levels.into_iter())}
+  }
+
+  /// delays the results one level
+  pub fn wrap(self) -> Self {
+     Bfs { levels: Box::new( iter::once( Vec::new()).chain( self.levels))}
+  }
+
+  /// level n of the result gathers the level n - i of `f( x)` for each `x` at level i
+  pub fn bind<U, F>(self, f: F) -> Bfs<'a, U>
+     where
+       F: 'a + Fn(T) -> Bfs<'a, U>,
+       U: 'a,
+  {
+     let mut source = Some(self.levels);
+     let mut active: Vec<Box<dyn 'a + Iterator<Item = Vec<U>>>> = Vec::new();
+     Bfs { levels: Box::new( iter::from_fn( move || {
+                 if let Some(level) = source.as_mut().and_then( |src| src.next()) {
+                     active.extend( level.into_iter().map( |x| f( x).levels));
+                 } else {
+                     source = None;
+                 }
+                 if source.is_none() && active.is_empty() {
+                     return None;
+                 }
+                 let mut out = Vec::new();
+                 active.retain_mut( |branch| match branch.next() {
+                                               Some(mut ys) => {out.append( &mut ys); true},
+                                               None => false,
+                                           });
+                 if out.is_empty() && source.is_none() && active.is_empty() {
+                     return None;
+                 }
+                 Some(out)
+               }))}
+  }
+
+  /// level-wise union
+  pub fn mplus(self, other: Self) -> Self {
+     let (mut xs, mut ys) = (self.levels.fuse(), other.levels.fuse());
+     Bfs { levels: Box::new( iter::from_fn( move || match (xs.next(), ys.next()) {
+                                       (None, None) => None,
+                                       (l1, l2) => {
+                                           let mut level = l1.unwrap_or_default();
+                                           level.extend( l2.unwrap_or_default());
+                                           Some(level)
+                                       },
+                                   }))}
+  }
+
+  /// the stream of results per level, shallowest first
+  pub fn levels(self) -> Box<dyn 'a + Iterator<Item = Vec<T>>> {
+     self.levels
+  }
+
+  /// drops the levels deeper than `depth`, making the search finite
+  pub fn to_depth(self, depth: usize) -> Self {
+     Bfs { levels: Box::new( self.levels.take( depth + 1))}
+  }
+
+  /// the first non empty level, or an empty vector if there is none;
+  /// an infinite search without results does not end, bound it with `to_depth`
+  pub fn shortest(mut self) -> Vec<T> {
+     self.levels.find( |level| !level.is_empty()).unwrap_or_default()
+  }
+}
+
+impl<'a> Bfs<'a, ()> {
+
+  pub fn guard(cond: bool) -> Self {
+     if cond {Bfs::pure(())} else {Bfs::mzero()}
+  }
+}
+
+/// alternatives one level deeper, a search step
+pub fn choose<'a, T: 'a, I>(xs: I) -> Bfs<'a, T>
+   where I: IntoIterator<Item = T>,
+{
+   Bfs::from_iter( xs).wrap()
+}
+
+/// alternatives at depth 0
+impl<'a, T: 'a> FromIterator<T> for Bfs<'a, T> {
+   fn from_iter<I: IntoIterator<Item = T>>(xs: I) -> Self {
+      Bfs { levels: Box::new( iter::once( xs.into_iter().collect()))}
+   }
+}
+
+/// results in level order
+impl<'a, T: 'a> IntoIterator for Bfs<'a, T> {
+   type Item = T;
+   type IntoIter = Flatten<Box<dyn 'a + Iterator<Item = Vec<T>>>>;
+
+   fn into_iter(self) -> Self::IntoIter {
+      self.levels.flatten()
+   }
+}
+
+impl<'a, T: 'a> Monad for Bfs<'a, T> {
+   fn pure(x: T) -> Self {
+      Bfs::pure( x)
+   }
+}
+
+impl<'a, T: 'a> MZero for Bfs<'a, T> {
+   fn mzero() -> Self {
+      Bfs::mzero()
+   }
+}
+
+impl<'a, T: 'a> MPlus for Bfs<'a, T> {
+   fn mplus(&mut self, other: &mut Self) {
+      let xs = mem::replace( self, Bfs::mzero());
+      let ys = mem::replace( other, Bfs::mzero());
+      *self = xs.mplus( ys);
+   }
+}
+
+//--------------------------------------------
+
+/// type constructor marker of `Bfs<'a, _>` for the traits in module hkt
+pub struct BfsK;
+
+impl<'a> Kind<'a> for BfsK {
+    type Of<A: 'a> = Bfs<'a, A>;
+}
+
+impl<'a, A: 'a> Kinded<'a> for Bfs<'a, A> {
+    type K = BfsK;
+    type Elem = A;
+
+    fn into_kind(self) -> Bfs<'a, A> { self }
+}
+
+impl<'a> Functor<'a> for BfsK {
+
+    fn fmap<A: 'a, B: 'a, F>(fa: Bfs<'a, A>, f: F) -> Bfs<'a, B>
+       where F: 'a + Fn(A) -> B
+    {
+       Bfs { levels: Box::new( fa.levels.map( move |level| level.into_iter().map( &f).collect()))}
+    }
+}
+
+impl<'a> Applicative<'a> for BfsK {
+
+    fn of<A: 'a + Clone>(x: A) -> Bfs<'a, A> {
+       Bfs::pure( x)
+    }
+
+    fn lift_a2<A: 'a + Clone, B: 'a + Clone, C: 'a, F>(fa: Bfs<'a, A>, fb: Bfs<'a, B>, f: F) -> Bfs<'a, C>
+       where F: 'a + Fn(A, B) -> C
+    {
+       // fb is traversed once per result of fa, so its levels are memoized as they are reached
+       let fb_memo = Rc::new( RefCell::new( (fb.levels, Vec::<Vec<B>>::new())));
+       let f = Rc::new( f);
+       fa.bind( move |a| {
+            let (memo, f) = (fb_memo.clone(), f.clone());
+            let replay = (0..).map_while( move |i| {
+                               let (src, cache) = &mut *memo.borrow_mut();
+                               if i == cache.len() {
+                                   cache.push( src.next()?);
+                               }
+                               Some(cache[i].clone())
+                            });
+            Bfs::from_levels( replay).bind( move |b| Bfs::pure( f( a.clone(), b)))
+       })
+    }
+}
+
+impl<'a> MonadK<'a> for BfsK {
+
+    fn flat_map<A: 'a + Clone, B: 'a, F>(fa: Bfs<'a, A>, f: F) -> Bfs<'a, B>
+       where F: 'a + Fn(A) -> Bfs<'a, B>
+    {
+       fa.bind( f)
+    }
+}
+
+/// macro for the breadth first monad `Bfs<'a, T>`, same syntax as `mdo`
+///
+/// Every source must be a `Bfs` value, as the ones of `choose` or `Bfs::from_iter`.
+///
+/// You can use:
+/// * `pure return_expresion`    to return an expression value
+/// * `monadic_expression`       to end with a `Bfs` expression
+/// * `v <- pure return_expresion`  to lift a rhs expression value with Bfs::pure(x)
+/// * `v <- monadic_expression`  to use the monad result
+/// * `_ <- monadic_expression`  to ignore the monad result
+/// * `let z = expression`       to combine monad results
+/// * `guard boolean_expression` to filter results
+/// * `pattern <- monadic_expression`  to destructure the monad result, dropping the results that don't match
+/// * `let pattern = expression`  to destructure an expression, dropping the branch if it doesn't match
+///
+#[macro_export]
+macro_rules! bfsdo {
+  (@bind [$($p:tt)*] $monad:expr ; $($rest:tt)*) => [Bfs::bind( ($monad), move |x| {
+                                                            #[allow(unreachable_patterns)]
+                                                            match x { $($p)* => bfsdo!($($rest)*), _ => Bfs::mzero() }
+                                                        } )];
+
+  (pure $e:expr                           ) => [Bfs::pure($e)];
+  (let $v:ident = $e:expr ; $($rest:tt)*) => [Bfs::pure($e).bind( move |$v| { bfsdo!($($rest)*)} )];
+  (let $p:pat = $e:expr ; $($rest:tt)*) => [Bfs::pure($e).bind( move |x| {
+                                                #[allow(unreachable_patterns)]
+                                                match x { $p => bfsdo!($($rest)*), _ => Bfs::mzero() }
+                                            } )];
+  (guard $boolean:expr ; $($rest:tt)*) => [Bfs::guard($boolean).bind( move |_| { bfsdo!($($rest)*)} )];
+  (_ <- $monad:expr ; $($rest:tt)* ) => [Bfs::bind( ($monad), move |_| { bfsdo!($($rest)*)} )];
+  ($v:ident <- pure $e:expr ; $($rest:tt)* ) => [Bfs::pure($e).bind( move |$v| { bfsdo!($($rest)*)} )];
+  ($v:ident <- $monad:expr ; $($rest:tt)* ) => [Bfs::bind( ($monad), move |$v| { bfsdo!($($rest)*)} )];
+  ($($tokens:tt)+                         ) => [$crate::split_pat_bind!(bfsdo $($tokens)+)];
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bfs::{Bfs, choose};
+    use crate::monad::MPlus;
+    use crate::hkt::lift_a2;
+    use std::iter::FromIterator;
+
+    fn nat<'a>() -> Bfs<'a, u32> {
+        Bfs::pure( 0).mplus( nat_from( 1))
+    }
+
+    fn nat_from<'a>(n: u32) -> Bfs<'a, u32> {
+        Bfs::from_levels( (n..).map( |x| vec![x])).wrap()
+    }
+
+    #[test]
+    fn test_levels() {
+        let bloc = bfsdo!{
+            x <- choose( 1..=2);
+            y <- choose( vec![x * 10, x * 100]).wrap();
+            guard y != 20;
+            pure (x, y)
+        };
+        assert_eq!( bloc.levels().collect::<Vec<_>>(), vec![vec![], vec![], vec![], vec![(1, 10), (1, 100), (2, 200)]]);
+
+        let mut xs = Bfs::from_iter( vec![1, 2]).wrap();
+        MPlus::mplus( &mut xs, &mut Bfs::from_iter( vec![3]));
+        assert_eq!( xs.into_iter().collect::<Vec<_>>(), vec![3, 1, 2]);
+
+        let pairs = lift_a2( choose( 0..2), choose( 0..2), |a, b| (a, b));
+        assert_eq!( pairs.levels().collect::<Vec<_>>(), vec![vec![], vec![], vec![(0, 0), (0, 1), (1, 0), (1, 1)]]);
+
+        let sums = lift_a2( nat(), nat(), |a, b| a + b).to_depth( 2);
+        assert_eq!( sums.levels().collect::<Vec<_>>(), vec![vec![0], vec![1, 1], vec![2, 2, 2]]);
+    }
+
+    #[test]
+    fn test_infinite_search() {
+        // the pairs of naturals adding up to 3, from infinite sources, with the depth
+        let sums = bfsdo!{
+            x <- nat();
+            y <- nat();
+            guard x + y == 3;
+            pure (x, y)
+        };
+        let levels = sums.to_depth( 4).levels().collect::<Vec<_>>();
+        assert_eq!( levels[3], vec![(0, 3), (1, 2), (2, 1), (3, 0)]);
+
+        let firsts = bfsdo!{
+            Some(x) <- Bfs::from_iter( vec![None, Some(5)]);
+            let (a, b) = (x, x + 1);
+            pure a * b
+        };
+        assert_eq!( firsts.shortest(), vec![30]);
+    }
+}
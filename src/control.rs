@@ -0,0 +1,361 @@
+//! Control.Monad style combinators, written once for every `hkt::MonadK` instance
+//!
+//! The monad type constructor is inferred through the trait `hkt::Kinded` of the arguments,
+//! so they apply to Option, Result, Vec, LinkedList, VecDeque, Reader, Writer, State,
+//! ReaderT, WriterT and StateT values alike.
+//!
+//! ```
+//! use monadic::control::{sequence, replicate_m, when};
+//!
+//! assert_eq!( sequence( vec![Some(1), Some(2)]), Some(vec![1, 2]));
+//! assert_eq!( sequence( vec![Ok(1), Err("e")]), Err("e"));
+//!
+//! let bits = replicate_m( 2, || vec![0, 1]);
+//! assert_eq!( bits, vec![vec![0, 0], vec![0, 1], vec![1, 0], vec![1, 1]]);
+//!
+//! assert_eq!( when( false, None), Some(()));
+//! ```
+
+use crate::hkt::{Kind, Kinded, Functor, Applicative, MonadK, MonadRecK};
+use crate::monad::{Alt, Step};
+use std::rc::Rc;
+
+/// evaluates the monadic values from left to right, collecting their results
+pub fn sequence<'a, I, M>(ms: I) -> <M::K as Kind<'a>>::Of<Vec<M::Elem>>
+   where
+     I: IntoIterator<Item = M>,
+     M: Kinded<'a>,
+     M::K: MonadK<'a>,
+     M::Elem: Clone,
+{
+   let mut acc = M::K::of( Vec::new());
+   for m in ms {
+      acc = M::K::lift_a2( acc, m.into_kind(), |mut xs: Vec<M::Elem>, x| { xs.push( x); xs });
+   }
+   acc
+}
+
+/// evaluates the monadic values from left to right, ignoring their results
+pub fn sequence_<'a, I, M>(ms: I) -> <M::K as Kind<'a>>::Of<()>
+   where
+     I: IntoIterator<Item = M>,
+     M: Kinded<'a>,
+     M::K: MonadK<'a>,
+     M::Elem: Clone,
+{
+   let mut acc = M::K::of( ());
+   for m in ms {
+      acc = M::K::lift_a2( acc, m.into_kind(), |_, _| ());
+   }
+   acc
+}
+
+/// maps each element to a monadic value and sequences them, as `sequence( xs.map( f))`
+pub fn traverse<'a, I, M, F>(f: F, xs: I) -> <M::K as Kind<'a>>::Of<Vec<M::Elem>>
+   where
+     I: IntoIterator,
+     F: Fn(I::Item) -> M,
+     M: Kinded<'a>,
+     M::K: MonadK<'a>,
+     M::Elem: Clone,
+{
+   sequence( xs.into_iter().map( f))
+}
+
+/// Haskell's mapM, same as `traverse`
+pub fn map_m<'a, I, M, F>(f: F, xs: I) -> <M::K as Kind<'a>>::Of<Vec<M::Elem>>
+   where
+     I: IntoIterator,
+     F: Fn(I::Item) -> M,
+     M: Kinded<'a>,
+     M::K: MonadK<'a>,
+     M::Elem: Clone,
+{
+   traverse( f, xs)
+}
+
+/// `map_m` with the arguments flipped
+pub fn for_m<'a, I, M, F>(xs: I, f: F) -> <M::K as Kind<'a>>::Of<Vec<M::Elem>>
+   where
+     I: IntoIterator,
+     F: Fn(I::Item) -> M,
+     M: Kinded<'a>,
+     M::K: MonadK<'a>,
+     M::Elem: Clone,
+{
+   traverse( f, xs)
+}
+
+/// `map_m` ignoring the results
+pub fn map_m_<'a, I, M, F>(f: F, xs: I) -> <M::K as Kind<'a>>::Of<()>
+   where
+     I: IntoIterator,
+     F: Fn(I::Item) -> M,
+     M: Kinded<'a>,
+     M::K: MonadK<'a>,
+     M::Elem: Clone,
+{
+   sequence_( xs.into_iter().map( f))
+}
+
+/// `for_m` ignoring the results
+pub fn for_m_<'a, I, M, F>(xs: I, f: F) -> <M::K as Kind<'a>>::Of<()>
+   where
+     I: IntoIterator,
+     F: Fn(I::Item) -> M,
+     M: Kinded<'a>,
+     M::K: MonadK<'a>,
+     M::Elem: Clone,
+{
+   sequence_( xs.into_iter().map( f))
+}
+
+/// sequences `n` monadic values produced by `gen`
+///
+/// It takes a generator as the boxed Reader and State values are not `Clone`.
+pub fn replicate_m<'a, M, G>(n: usize, gen: G) -> <M::K as Kind<'a>>::Of<Vec<M::Elem>>
+   where
+     G: Fn() -> M,
+     M: Kinded<'a>,
+     M::K: MonadK<'a>,
+     M::Elem: Clone,
+{
+   sequence( (0..n).map( |_| gen()))
+}
+
+/// runs the monadic action only if the condition holds, otherwise returns `()` lifted
+pub fn when<'a, M>(cond: bool, m: M) -> <M::K as Kind<'a>>::Of<()>
+   where
+     M: Kinded<'a, Elem = ()>,
+     M::K: MonadK<'a>,
+{
+   if cond {m.into_kind()} else {M::K::of( ())}
+}
+
+/// runs the monadic action only if the condition does not hold
+pub fn unless<'a, M>(cond: bool, m: M) -> <M::K as Kind<'a>>::Of<()>
+   where
+     M: Kinded<'a, Elem = ()>,
+     M::K: MonadK<'a>,
+{
+   when( !cond, m)
+}
+
+/// a success of the action as `Some`, with `None` as alternative (`Some <$> m <|> pure None`)
+///
+/// With Option and Result it never fails; with the list monads it appends `None` to the results.
+pub fn optional<'a, M>(m: M) -> <M::K as Kind<'a>>::Of<Option<M::Elem>>
+   where
+     M: Kinded<'a>,
+     M::K: Applicative<'a>,
+     M::Elem: Clone,
+     <M::K as Kind<'a>>::Of<Option<M::Elem>>: Alt,
+{
+   M::K::fmap( m.into_kind(), Some).alt_with( || M::K::of( None))
+}
+
+/// keeps the elements for which the monadic predicate holds
+///
+/// With the list monads it returns every combination, e.g. the powerset with `|_| vec![true, false]`.
+pub fn filter_m<'a, I, M, P>(p: P, xs: I) -> <M::K as Kind<'a>>::Of<Vec<I::Item>>
+   where
+     I: IntoIterator,
+     I::Item: 'a + Clone,
+     P: Fn(I::Item) -> M,
+     M: Kinded<'a, Elem = bool>,
+     M::K: MonadK<'a>,
+{
+   let mut acc = M::K::of( Vec::new());
+   for x in xs {
+      let keep = p( x.clone()).into_kind();
+      acc = M::K::lift_a2( acc, keep, move |mut ys: Vec<I::Item>, keep| { 
+                if keep { ys.push( x.clone())}
+                ys 
+            });
+   }
+   acc
+}
+
+/// left fold where the accumulating function returns a monadic value
+pub fn fold_m<'a, I, M, F>(f: F, z: M::Elem, xs: I) -> <M::K as Kind<'a>>::Of<M::Elem>
+   where
+     I: IntoIterator,
+     I::Item: 'a + Clone,
+     F: 'a + Fn(M::Elem, I::Item) -> M,
+     M: Kinded<'a>,
+     M::K: MonadK<'a>,
+     M::Elem: Clone,
+{
+   let f = Rc::new( f);
+   let mut acc = M::K::of( z);
+   for x in xs {
+      let f = f.clone();
+      acc = M::K::flat_map( acc, move |b| f( b, x.clone()).into_kind());
+   }
+   acc
+}
+
+/// `fold_m` ignoring the result
+pub fn fold_m_<'a, I, M, F>(f: F, z: M::Elem, xs: I) -> <M::K as Kind<'a>>::Of<()>
+   where
+     I: IntoIterator,
+     I::Item: 'a + Clone,
+     F: 'a + Fn(M::Elem, I::Item) -> M,
+     M: Kinded<'a>,
+     M::K: MonadK<'a>,
+     M::Elem: Clone,
+{
+   M::K::fmap( fold_m( f, z, xs), |_| ())
+}
+
+/// sequences the monadic values of `f` applied to the pairs of elements, stopping at the shortest
+pub fn zip_with_m<'a, I, J, M, F>(f: F, xs: I, ys: J) -> <M::K as Kind<'a>>::Of<Vec<M::Elem>>
+   where
+     I: IntoIterator,
+     J: IntoIterator,
+     F: Fn(I::Item, J::Item) -> M,
+     M: Kinded<'a>,
+     M::K: MonadK<'a>,
+     M::Elem: Clone,
+{
+   sequence( xs.into_iter().zip( ys).map( |(x, y)| f( x, y)))
+}
+
+/// `zip_with_m` ignoring the results
+pub fn zip_with_m_<'a, I, J, M, F>(f: F, xs: I, ys: J) -> <M::K as Kind<'a>>::Of<()>
+   where
+     I: IntoIterator,
+     J: IntoIterator,
+     F: Fn(I::Item, J::Item) -> M,
+     M: Kinded<'a>,
+     M::K: MonadK<'a>,
+     M::Elem: Clone,
+{
+   sequence_( xs.into_iter().zip( ys).map( |(x, y)| f( x, y)))
+}
+
+/// repeats the monadic actions produced by `gen` indefinitely
+///
+/// It ends with the monads that can stop the sequence: at the first None or Err, or when a collection is empty.
+/// It runs as a `tail_rec_m` loop, in constant stack whatever the number of repetitions.
+pub fn forever<'a, M, B, G>(gen: G) -> <M::K as Kind<'a>>::Of<B>
+   where
+     G: 'a + Fn() -> M,
+     M: Kinded<'a>,
+     M::K: MonadRecK<'a>,
+     B: 'a,
+{
+   M::K::tail_rec_m( (), move |_| M::K::fmap( gen().into_kind(), |_| Step::Loop( ())))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::control::{sequence, traverse, map_m, replicate_m, unless, 
+                         filter_m, zip_with_m, forever, optional};
+    use std::cell::Cell;
+
+    #[test]
+    fn test_sequence_iterables() {
+        assert_eq!( sequence( vec![Some(1), None, Some(3)]), None);
+        assert_eq!( sequence( vec![vec![1, 2], vec![3]]), vec![vec![1, 3], vec![2, 3]]);
+        assert_eq!( replicate_m( 2, || vec![0, 1]), vec![vec![0, 0], vec![0, 1], vec![1, 0], vec![1, 1]]);
+    }
+
+    #[cfg(all(feature="reader", feature="writer"))]
+    #[test]
+    fn test_sequence_reader_writer() {
+        use crate::control::when;
+        use crate::reader::{Reader, ask};
+        use crate::writer::{Writer, tell_str};
+        
+        let rdr: Reader<'_, i32, Vec<i32>> = sequence( vec![ask(), Reader::pure( 5)]);
+        assert_eq!( rdr.initial_env( 2), vec![2, 5]);
+
+        let wr = sequence( vec![tell_str( "a"), tell_str( "b"), when( false, tell_str( "c"))]);
+        assert_eq!( wr.unwrap_pair(), (vec![(), (), ()], String::from( "ab")));
+
+        let wr1: Writer<(), String> = when( true, tell_str( "c"));
+        assert_eq!( wr1.unwrap_pair(), ((), String::from( "c")));
+    }
+
+    #[test]
+    fn test_traverse() {
+        assert_eq!( traverse( |s: &str| s.parse::<i32>().ok(), vec!["1", "2"]), Some(vec![1, 2]));
+        assert!( map_m( |s: &str| s.parse::<i32>(), vec!["1", "x"]).is_err());
+    }
+
+    #[cfg(all(feature="reader_trans", feature="writer_trans", feature="state_trans"))]
+    #[test]
+    fn test_sequence_transformers() {
+        use crate::reader_trans::ReaderT;
+        use crate::writer_trans::WriterT;
+        use crate::state_trans::{self, StateT};
+        
+        let rdt: ReaderT<'_, i32, Vec<i32>> = ReaderT::lift( vec![1, 2]);
+        let res = sequence( vec![rdt, ReaderT::lift( vec![10])]);
+        assert_eq!( res.initial_env( 0), vec![vec![1, 10], vec![2, 10]]);
+
+        let wrt: WriterT<Vec<i32>> = WriterT::lift( vec![1, 2]);
+        let res = sequence( vec![wrt, WriterT::lift( vec![3])]);
+        assert_eq!( res.unwrap(), vec![vec![1, 3], vec![2, 3]]);
+
+        let stt = replicate_m( 2, state_trans::get::<i32>);
+        let res: Vec<(Vec<Vec<i32>>, i32)> = sequence( vec![stt, StateT::lift( vec![vec![7]])]).initial_state( 4);
+        assert_eq!( res, vec![(vec![vec![4, 4], vec![7]], 4)]);
+    }
+
+    #[test]
+    fn test_control_flow() {
+        let powerset = filter_m( |_| vec![true, false], vec![1, 2, 3]);
+        assert_eq!( powerset, vec![vec![1, 2, 3], vec![1, 2], vec![1, 3], vec![1], vec![2, 3], vec![2], vec![3], vec![]]);
+        
+        assert_eq!( zip_with_m( |x: i32, y: i32| x.checked_sub( y), vec![3, 2], vec![1, 1, 1]), Some(vec![2, 1]));
+        assert_eq!( unless( true, None), Some(()));
+    }
+
+    #[test]
+    fn test_forever() {
+        const N: u32 = 1_000_000;
+        
+        let n = Cell::new( 0);
+        let res: Result<(), u32> = forever( || { n.set( n.get() + 1); if n.get() < N {Ok(())} else {Err( n.get())} });
+        assert_eq!( res, Err(N));
+        
+        n.set( 0);
+        let res: Option<()> = forever( || { n.set( n.get() + 1); if n.get() < N {Some(())} else {None} });
+        assert_eq!( res, None);
+        assert_eq!( n.get(), N);
+        
+        n.set( 0);
+        let res: Vec<()> = forever( || { n.set( n.get() + 1); if n.get() < N {vec![()]} else {vec![]} });
+        assert!( res.is_empty());
+        assert_eq!( n.get(), N);
+    }
+
+    #[cfg(all(feature="reader", feature="writer", feature="state"))]
+    #[test]
+    fn test_control_flow_reader_writer_state() {
+        use crate::control::{fold_m, fold_m_};
+        use crate::reader::{Reader, ask};
+        use crate::writer::{Writer, tell};
+        use crate::state::{State, get, put};
+        
+        let evens: Reader<'_, i32, Vec<i32>> = filter_m( |x| ask().bind( move |m: i32| Reader::pure( x % m == 0)), 1..7);
+        assert_eq!( evens.initial_env( 2), vec![2, 4, 6]);
+        
+        let sum_st = fold_m( |acc, x| get().bind( move |n: i32| put( n + 1).bind( move |_| State::pure( acc + x))), 0, 1..5);
+        assert_eq!( sum_st.initial_state( 0), (10, 4));
+        
+        let logged = fold_m_( |acc: i32, x: i32| tell( vec![acc]).bind( move |_| Writer::pure( acc + x)), 0, 1..4);
+        assert_eq!( logged.unwrap_pair(), ((), vec![0, 1, 3]));
+    }
+
+    #[test]
+    fn test_optional() {
+        assert_eq!( optional( Some(1)), Some(Some(1)));
+        assert_eq!( optional( None::<i32>), Some(None));
+        assert_eq!( optional( "x".parse::<i32>().map_err( |_| ())), Ok(None));
+        assert_eq!( optional( "7".parse::<i32>()), Ok(Some(7)));
+        assert_eq!( optional( vec![1, 2]), vec![Some(1), Some(2), None]);
+    }
+}
@@ -0,0 +1,447 @@
+//! Functor, Applicative and Monad traits over type constructors (higher kinded types emulation)
+//!
+//! Rust has no higher kinded types, so a type constructor like `Option` or `Reader<'a, E, _>`
+//! is represented by a marker type (`OptionK`, `ReaderK<E>`, ...) implementing `Kind`,
+//! whose generic associated type `Of<A>` applies the constructor to `A`.
+//!
+//! The trait `Kinded` links a concrete type back to its constructor and element,
+//! so that the free functions `fmap`, `lift_a2` and `ap` infer the constructor from their arguments.
+//!
+//! The trait `MonadK` adds `flat_map` so that generic code (module `control`) runs on every monad of the crate:
+//! the iterable monads, Reader, Writer, State and the transformers ReaderT, WriterT and StateT.
+//!
+//! The macro `ado` combines independent steps through `lift_a2` and `fmap` without monadic sequencing.
+//!
+//! ```
+//! # #[macro_use] extern crate monadic;
+//! use monadic::hkt::{fmap, lift_a2};
+//!
+//! # fn main() {
+//! assert_eq!( fmap( Some(2), |x| x + 1), Some(3));
+//! assert_eq!( lift_a2( vec![1, 2], vec![10, 20], |x, y| x + y), vec![11, 21, 12, 22]);
+//!
+//! let res = ado!{
+//!     x <- Some(1);
+//!     y <- Some(2);
+//!     pure x + y
+//! };
+//! assert_eq!( res, Some(3));
+//! # }
+//! ```
+
+use crate::monad::{self, Step};
+use std::collections::{LinkedList, VecDeque};
+use std::marker::PhantomData;
+use std::iter::FromIterator;
+
+/// A type constructor marker; `Of<A>` is the constructor applied to `A`
+pub trait Kind<'a> {
+    type Of<A: 'a>;
+}
+
+/// A concrete type seen as a type constructor `K` applied to an element type `Elem`
+pub trait Kinded<'a> {
+    type K: Kind<'a>;
+    type Elem: 'a;
+
+    fn into_kind(self) -> <Self::K as Kind<'a>>::Of<Self::Elem>;
+}
+
+pub trait Functor<'a>: Kind<'a> {
+
+    fn fmap<A: 'a, B: 'a, F>(fa: Self::Of<A>, f: F) -> Self::Of<B>
+       where
+         F: 'a + Fn(A) -> B;
+}
+
+/// Elements are required to be `Clone` as the list instances combine each element with many others
+/// and the boxed Reader and State instances may run several times.
+pub trait Applicative<'a>: Functor<'a> {
+
+    /// named `of` as `pure` would be ambiguous with `Monad::pure` for the types implementing both
+    fn of<A: 'a + Clone>(x: A) -> Self::Of<A>;
+
+    fn lift_a2<A: 'a + Clone, B: 'a + Clone, C: 'a, F>(fa: Self::Of<A>, fb: Self::Of<B>, f: F) -> Self::Of<C>
+       where
+         F: 'a + Fn(A, B) -> C;
+
+    fn ap<A: 'a + Clone, B: 'a, G>(fg: Self::Of<G>, fa: Self::Of<A>) -> Self::Of<B>
+       where
+         G: 'a + Clone + Fn(A) -> B,
+    {
+       Self::lift_a2( fg, fa, |g, a| g(a))
+    }
+}
+
+/// Monad over a type constructor marker, named `MonadK` to be distinguished from `monad::Monad`
+/// which is tied to IntoIterator.
+pub trait MonadK<'a>: Applicative<'a> {
+
+    fn flat_map<A: 'a + Clone, B: 'a, F>(fa: Self::Of<A>, f: F) -> Self::Of<B>
+       where
+         F: 'a + Fn(A) -> Self::Of<B>;
+}
+
+/// collection type constructors, whose values can be converted to and from a Vec
+///
+/// Required for the inner monad of the WriterT instance, that must collect the logs of each branch.
+pub trait ListKind<'a>: MonadK<'a> {
+
+    fn to_vec<A: 'a>(fa: Self::Of<A>) -> Vec<A>;
+
+    fn from_vec<A: 'a>(v: Vec<A>) -> Self::Of<A>;
+}
+
+/// monads with a stack safe `tail_rec_m` loop, required by `control::forever`
+///
+/// Implemented for the monads that can stop a loop by themselves: Option, Result and the collections.
+pub trait MonadRecK<'a>: MonadK<'a> {
+
+    fn tail_rec_m<A: 'a, B: 'a, F>(a: A, f: F) -> Self::Of<B>
+       where
+         F: 'a + Fn(A) -> Self::Of<Step<A, B>>;
+}
+
+//--------------------------------------------
+// free functions inferring the type constructor from the arguments
+
+pub fn fmap<'a, M, B, F>(m: M, f: F) -> <M::K as Kind<'a>>::Of<B>
+   where
+     M: Kinded<'a>,
+     M::K: Functor<'a>,
+     B: 'a,
+     F: 'a + Fn(M::Elem) -> B,
+{
+   M::K::fmap( m.into_kind(), f)
+}
+
+pub fn lift_a2<'a, M, N, C, F>(m: M, n: N, f: F) -> <M::K as Kind<'a>>::Of<C>
+   where
+     M: Kinded<'a>,
+     M::K: Applicative<'a>,
+     M::Elem: Clone,
+     N: Kinded<'a, K = M::K>,
+     N::Elem: Clone,
+     C: 'a,
+     F: 'a + Fn(M::Elem, N::Elem) -> C,
+{
+   M::K::lift_a2( m.into_kind(), n.into_kind(), f)
+}
+
+/// applies the functions in the first argument to the values in the second
+pub fn ap<'a, M, N, B>(mg: M, n: N) -> <M::K as Kind<'a>>::Of<B>
+   where
+     M: Kinded<'a>,
+     M::K: Applicative<'a>,
+     M::Elem: Clone + Fn(N::Elem) -> B,
+     N: Kinded<'a, K = M::K>,
+     N::Elem: Clone,
+     B: 'a,
+{
+   M::K::ap( mg.into_kind(), n.into_kind())
+}
+
+pub fn flat_map<'a, M, B, F>(m: M, f: F) -> <M::K as Kind<'a>>::Of<B>
+   where
+     M: Kinded<'a>,
+     M::K: MonadK<'a>,
+     M::Elem: Clone,
+     B: 'a,
+     F: 'a + Fn(M::Elem) -> <M::K as Kind<'a>>::Of<B>,
+{
+   M::K::flat_map( m.into_kind(), f)
+}
+
+/// combines iterables element-wise (the ZipList applicative), stopping at the shortest
+pub fn zip_with<I, J, C, F>(xs: I, ys: J, f: F) -> impl Iterator<Item = C>
+   where
+     I: IntoIterator,
+     J: IntoIterator,
+     F: Fn(I::Item, J::Item) -> C,
+{
+   xs.into_iter().zip( ys).map( move |(x, y)| f(x, y))
+}
+
+//--------------------------------------------
+
+pub struct OptionK;
+
+impl<'a> Kind<'a> for OptionK {
+    type Of<A: 'a> = Option<A>;
+}
+
+impl<'a, T: 'a> Kinded<'a> for Option<T> {
+    type K = OptionK;
+    type Elem = T;
+
+    fn into_kind(self) -> Option<T> { self }
+}
+
+impl<'a> Functor<'a> for OptionK {
+
+    fn fmap<A: 'a, B: 'a, F>(fa: Option<A>, f: F) -> Option<B>
+       where F: 'a + Fn(A) -> B
+    {
+       fa.map( f)
+    }
+}
+
+impl<'a> Applicative<'a> for OptionK {
+
+    fn of<A: 'a + Clone>(x: A) -> Option<A> { Some(x) }
+
+    fn lift_a2<A: 'a + Clone, B: 'a + Clone, C: 'a, F>(fa: Option<A>, fb: Option<B>, f: F) -> Option<C>
+       where F: 'a + Fn(A, B) -> C
+    {
+       match (fa, fb) {
+         (Some(a), Some(b)) => Some( f(a, b)),
+         _ => None,
+       }
+    }
+}
+
+impl<'a> MonadK<'a> for OptionK {
+
+    fn flat_map<A: 'a + Clone, B: 'a, F>(fa: Option<A>, f: F) -> Option<B>
+       where F: 'a + Fn(A) -> Option<B>
+    {
+       fa.and_then( f)
+    }
+}
+
+impl<'a> MonadRecK<'a> for OptionK {
+
+    fn tail_rec_m<A: 'a, B: 'a, F>(a: A, f: F) -> Option<B>
+       where F: 'a + Fn(A) -> Option<Step<A, B>>
+    {
+       monad::tail_rec_m( a, f).next()
+    }
+}
+
+//--------------------------------------------
+
+pub struct ResultK<E>(PhantomData<E>);
+
+impl<'a, E> Kind<'a> for ResultK<E> {
+    type Of<A: 'a> = Result<A, E>;
+}
+
+impl<'a, T: 'a, E> Kinded<'a> for Result<T, E> {
+    type K = ResultK<E>;
+    type Elem = T;
+
+    fn into_kind(self) -> Result<T, E> { self }
+}
+
+impl<'a, E> Functor<'a> for ResultK<E> {
+
+    fn fmap<A: 'a, B: 'a, F>(fa: Result<A, E>, f: F) -> Result<B, E>
+       where F: 'a + Fn(A) -> B
+    {
+       fa.map( f)
+    }
+}
+
+impl<'a, E> Applicative<'a> for ResultK<E> {
+
+    fn of<A: 'a + Clone>(x: A) -> Result<A, E> { Ok(x) }
+
+    /// returns the first `Err`
+    fn lift_a2<A: 'a + Clone, B: 'a + Clone, C: 'a, F>(fa: Result<A, E>, fb: Result<B, E>, f: F) -> Result<C, E>
+       where F: 'a + Fn(A, B) -> C
+    {
+       let a = fa?;
+       let b = fb?;
+       Ok( f(a, b))
+    }
+}
+
+impl<'a, E> MonadK<'a> for ResultK<E> {
+
+    fn flat_map<A: 'a + Clone, B: 'a, F>(fa: Result<A, E>, f: F) -> Result<B, E>
+       where F: 'a + Fn(A) -> Result<B, E>
+    {
+       fa.and_then( f)
+    }
+}
+
+impl<'a, E> MonadRecK<'a> for ResultK<E> {
+
+    /// returns the first `Err`
+    fn tail_rec_m<A: 'a, B: 'a, F>(a: A, f: F) -> Result<B, E>
+       where F: 'a + Fn(A) -> Result<Step<A, B>, E>
+    {
+       monad::result_tail_rec_m( a, f)
+    }
+}
+
+//--------------------------------------------
+// list instances, lift_a2 combines every element of the first with every element of the second
+
+// in the iteration order of `mdo`, as nested flat_maps
+fn cartesian_with<A, B, C, I, J, F, O>(xs: I, ys: J, f: F) -> O
+   where
+     A: Clone,
+     B: Clone,
+     I: IntoIterator<Item = A>,
+     J: IntoIterator<Item = B>,
+     F: Fn(A, B) -> C,
+     O: FromIterator<C>,
+{
+   let ys = &ys.into_iter().collect::<Vec<B>>();
+   let f = &f;
+   xs.into_iter().flat_map( |a| ys.iter().map( move |b| f( a.clone(), b.clone()))).collect::<O>()
+}
+
+macro_rules! list_kind_instances {
+  ($kind:ident, $list:ident) => {
+
+    pub struct $kind;
+
+    impl<'a> Kind<'a> for $kind {
+        type Of<A: 'a> = $list<A>;
+    }
+
+    impl<'a, T: 'a> Kinded<'a> for $list<T> {
+        type K = $kind;
+        type Elem = T;
+
+        fn into_kind(self) -> $list<T> { self }
+    }
+
+    impl<'a> Functor<'a> for $kind {
+
+        fn fmap<A: 'a, B: 'a, F>(fa: $list<A>, f: F) -> $list<B>
+           where F: 'a + Fn(A) -> B
+        {
+           fa.into_iter().map( f).collect()
+        }
+    }
+
+    impl<'a> Applicative<'a> for $kind {
+
+        fn of<A: 'a + Clone>(x: A) -> $list<A> {
+           std::iter::once( x).collect()
+        }
+
+        fn lift_a2<A: 'a + Clone, B: 'a + Clone, C: 'a, F>(fa: $list<A>, fb: $list<B>, f: F) -> $list<C>
+           where F: 'a + Fn(A, B) -> C
+        {
+           cartesian_with( fa, fb, f)
+        }
+    }
+
+    impl<'a> MonadK<'a> for $kind {
+
+        fn flat_map<A: 'a + Clone, B: 'a, F>(fa: $list<A>, f: F) -> $list<B>
+           where F: 'a + Fn(A) -> $list<B>
+        {
+           fa.into_iter().flat_map( f).collect()
+        }
+    }
+
+    impl<'a> MonadRecK<'a> for $kind {
+
+        fn tail_rec_m<A: 'a, B: 'a, F>(a: A, f: F) -> $list<B>
+           where F: 'a + Fn(A) -> $list<Step<A, B>>
+        {
+           monad::tail_rec_m( a, f).collect()
+        }
+    }
+
+    impl<'a> ListKind<'a> for $kind {
+
+        fn to_vec<A: 'a>(fa: $list<A>) -> Vec<A> {
+           fa.into_iter().collect()
+        }
+
+        fn from_vec<A: 'a>(v: Vec<A>) -> $list<A> {
+           v.into_iter().collect()
+        }
+    }
+  }
+}
+
+list_kind_instances!( VecK, Vec);
+list_kind_instances!( LinkedListK, LinkedList);
+list_kind_instances!( VecDequeK, VecDeque);
+
+//--------------------------------------------
+
+/// macro for applicative blocks of independent steps, combined with `lift_a2` and mapped with `fmap`
+///
+/// The monad expressions cannot refer to the variables bound in previous steps,
+/// which are only available in the ending `pure` expression. The bound values must be `Clone`.
+///
+/// You can use:
+/// * `v <- applicative_expression`  to bind the result
+/// * `_ <- applicative_expression`  to ignore the result
+/// * `pure return_expresion`    to end with an expression of the bound variables
+///
+/// ```
+/// # #[macro_use] extern crate monadic;
+/// # fn main() {
+/// let pairs = ado!{
+///     x <- vec![1, 2];
+///     y <- vec!['a', 'b'];
+///     pure (x, y)
+/// };
+/// assert_eq!( pairs, vec![(1, 'a'), (1, 'b'), (2, 'a'), (2, 'b')]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! ado {
+  (@acc ($p:pat) ($acc:expr) pure $e:expr                           ) => [$crate::hkt::fmap( $acc, move |$p| $e)];
+  (@acc ($p:pat) ($acc:expr) $v:tt <- $monad:expr ; $($rest:tt)* ) => [$crate::ado!(@acc (($p, $v)) ($crate::hkt::lift_a2( $acc, $monad, |a, b| (a, b))) $($rest)*)];
+  ($v:tt <- $monad:expr ; $($rest:tt)* ) => [$crate::ado!(@acc ($v) ($monad) $($rest)*)];
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hkt::{Applicative, VecK, fmap, lift_a2, ap, flat_map, zip_with};
+    use std::collections::LinkedList;
+
+    #[test]
+    fn test_functor_instances() {
+        assert_eq!( fmap( None::<i32>, |x| x + 1), None);
+        assert_eq!( fmap( Ok::<i32, ()>(1), |x| x * 2), Ok(2));
+        assert_eq!( fmap( vec![1, 2, 3], |x| x * 2), vec![2, 4, 6]);
+        assert_eq!( fmap( (1..3).collect::<LinkedList<_>>(), |x| x * 2).into_iter().collect::<Vec<_>>(), vec![2, 4]);
+    }
+
+    #[test]
+    fn test_applicative_instances() {
+        assert_eq!( VecK::of( 'a'), vec!['a']);
+        assert_eq!( lift_a2( Ok(1), Err::<i32, &str>("e"), |x, y| x + y), Err("e"));
+        assert_eq!( ap( vec![|x: i32| x + 1, |x: i32| x * 10], vec![1, 2]), vec![2, 3, 10, 20]);
+        assert_eq!( zip_with( 1..4, vec![10, 20], |x, y| x + y).collect::<Vec<_>>(), vec![11, 22]);
+    }
+
+    #[test]
+    fn test_monad_instances() {
+        assert_eq!( flat_map( vec![1, 2], |x| vec![x; x]), vec![1, 2, 2]);
+        assert_eq!( flat_map( Some(4), |x| if x > 3 {None} else {Some(x)}), None::<i32>);
+        assert_eq!( flat_map( Ok::<i32, &str>(4), |x| Err::<i32, _>( if x > 3 {"big"} else {"small"})), Err("big"));
+    }
+
+    #[test]
+    fn test_ado_vs_mdo() {
+        use crate::mdo;
+        use crate::monad::{Bind, Monad};
+
+        let xs = ado!{
+            x <- vec![1, 2];
+            _ <- vec![(), ()];
+            y <- vec![3, 4];
+            pure (x, y)
+        };
+        let ys = mdo!{
+            x <- vec![1, 2];
+            _ <- vec![(), ()];
+            y <- vec![3, 4];
+            pure (x, y)
+        }.collect::<Vec<_>>();
+
+        assert_eq!( xs, ys);
+    }
+}
@@ -15,7 +15,24 @@
 //!
 //! Iterator and IntoIterator trait imports are [predefined](https://doc.rust-lang.org/std/prelude/index.html#prelude-contents)
 //!
-//! There are also Reader, Writer and State monads in their respective modules with their own macros.
+//! The macro "resdo" binds `Result` values through `and_then`, returning the first `Err` instead of an empty iterator,
+//! and the macro "optdo" binds `Option` values through `and_then`, returning an `Option`.
+//! The macro "setdo" binds `HashSet` or `BTreeSet` sources collecting into a set after each step, merging duplicate branches.
+//!
+//! Module `hkt` defines Functor, Applicative and MonadK traits over type constructor markers, with the applicative macro "ado",
+//! and module `control` generic combinators for every monad of the crate.
+//!
+//! Module `logic` adds fair disjunction and conjunction for infinite iterables, with the macro "fairdo",
+//! and module `bfs` a breadth first search monad with the macro "bfsdo".
+//! Module `list` has the boxed and clonable list monad `ListM`, that `mdo` produces from a `ListM` source,
+//! module `stream` the memoizing lazy stream monad `Stream`, evaluating a comprehension at most once,
+//! and module `query` has the macro "qdo" for comprehensions with SQL like sort, group by, take, drop and distinct stages.
+//!
+//! There are also Reader, Writer and State monads in their respective modules with their own macros,
+//! and the statically dispatched `ReaderFn` and `StateFn` with the macros "static_rdrdo" and "static_stdo".
+//! Module `state_mut` has the in place State monad `StateMut` with the macro "stmdo",
+//! and module `services` a typed service map environment for Reader dependency injection.
+//! Every monad has a stack safe loop `tail_rec_m` stepping through `monad::Step` values.
 //!
 //! ```no_run
 //! # #[macro_use] extern crate monadic;
@@ -56,6 +73,8 @@
 //! ```
 
 pub mod monad;
+pub mod hkt;
+pub mod control;
 pub mod mio;
 
 #[cfg(feature="reader")]
@@ -64,6 +83,9 @@ pub mod reader;
 #[cfg(feature="reader_trans")]
 pub mod reader_trans;
 
+#[cfg(feature="services")]
+pub mod services;
+
 #[cfg(feature="writer")]
 pub mod writer;
 
@@ -77,6 +99,24 @@ pub mod state;
 #[cfg(feature="state_trans")]
 pub mod state_trans;
 
+#[cfg(feature="state_mut")]
+pub mod state_mut;
+
+#[cfg(feature="logic")]
+pub mod logic;
+
+#[cfg(feature="bfs")]
+pub mod bfs;
+
+#[cfg(feature="query")]
+pub mod query;
+
+#[cfg(feature="list")]
+pub mod list;
+
+#[cfg(feature="stream")]
+pub mod stream;
+
 #[cfg(any(feature="writer", feature="writer_trans"))]
 pub mod util;
 
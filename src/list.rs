@@ -0,0 +1,239 @@
+//! A boxed, lazily evaluated and cheaply clonable list monad
+//!
+//! The result of `mdo` is a nested `FlatMap` type with closures that cannot be named.
+//! `ListM<'a, T>` holds a shared generator of boxed iterators, so it can be returned from functions,
+//! stored in structures, cloned (sharing the generator) and iterated again, re-running the comprehension.
+//!
+//! Its inherent `bind` takes precedence over `Bind::bind`, so a `mdo` block whose first source is a `ListM`
+//! produces a `ListM`.
+//!
+//! ```
+//! # #[macro_use] extern crate monadic;
+//! use monadic::{mdo, monad::{Bind, Monad}, list::ListM};
+//!
+//! # fn main() {
+//! fn pythagorean(n: u32) -> ListM<'static, (u32, u32, u32)> {
+//!     mdo!{
+//!         z <- ListM::new( move || 1..=n);
+//!         x <- 1..=z;
+//!         y <- x..=z;
+//!         guard x * x + y * y == z * z;
+//!         pure (x, y, z)
+//!     }
+//! }
+//!
+//! let triples = pythagorean( 13);
+//! assert_eq!( triples.to_vec(), vec![(3, 4, 5), (6, 8, 10), (5, 12, 13)]);
+//! assert_eq!( triples.clone().into_iter().count(), 3);
+//! # }
+//! ```
+
+use crate::monad::{Monad, MZero, MPlus};
+use crate::hkt::{Kind, Kinded, Functor, Applicative, MonadK};
+use std::iter::{self, FromIterator};
+use std::rc::Rc;
+
+pub struct ListM<'a, T> {
+  gen: Rc<dyn 'a + Fn() -> Box<dyn 'a + Iterator<Item = T>>>,
+}
+
+impl<'a, T> Clone for ListM<'a, T> {
+  fn clone(&self) -> Self {
+     ListM { gen: self.gen.clone()}
+  }
+}
+
+impl<'a, T: 'a> ListM<'a, T> {
+
+  /// from a generator of iterators, called on each traversal
+  pub fn new<I, G>(gen: G) -> Self
+    where
+      G: 'a + Fn() -> I,
+      I: 'a + IntoIterator<Item = T>,
+  {
+     ListM { gen: Rc::new( move || Box::new( gen().into_iter()))}
+  }
+
+  pub fn pure(x: T) -> Self
+    where T: Clone
+  {
+     ListM::new( move || iter::once( x.clone()))
+  }
+
+  pub fn mzero() -> Self {
+     ListM::new( iter::empty)
+  }
+
+  /// lazy bind, where the continuation may return any IntoIterator, as the ones of `mdo`
+  pub fn bind<U, F>(self, f: F) -> ListM<'a, U::Item>
+     where
+       F: 'a + Fn(T) -> U,
+       U: 'a + IntoIterator,
+  {
+     let f = Rc::new( f);
+     ListM::new( move || {
+        let f = f.clone();
+        (self.gen)().flat_map( move |x| f( x))
+     })
+  }
+
+  /// concatenation, sharing both generators
+  pub fn mplus(self, other: Self) -> Self {
+     ListM::new( move || (self.gen)().chain( (other.gen)()))
+  }
+
+  /// a new traversal of the list
+  pub fn iter(&self) -> Box<dyn 'a + Iterator<Item = T>> {
+     (self.gen)()
+  }
+
+  pub fn to_vec(&self) -> Vec<T> {
+     self.iter().collect()
+  }
+}
+
+impl<'a, T: 'a + Clone> From<Vec<T>> for ListM<'a, T> {
+  fn from(xs: Vec<T>) -> Self {
+     let xs = Rc::new( xs);
+     ListM::new( move || {
+        let xs = xs.clone();
+        (0..xs.len()).map( move |i| xs[i].clone())
+     })
+  }
+}
+
+impl<'a, T: 'a + Clone> FromIterator<T> for ListM<'a, T> {
+  fn from_iter<I: IntoIterator<Item = T>>(xs: I) -> Self {
+     ListM::from( xs.into_iter().collect::<Vec<T>>())
+  }
+}
+
+impl<'a, T: 'a> IntoIterator for ListM<'a, T> {
+  type Item = T;
+  type IntoIter = Box<dyn 'a + Iterator<Item = T>>;
+
+  fn into_iter(self) -> Self::IntoIter {
+     (self.gen)()
+  }
+}
+
+impl<'a, T: 'a> IntoIterator for &ListM<'a, T> {
+  type Item = T;
+  type IntoIter = Box<dyn 'a + Iterator<Item = T>>;
+
+  fn into_iter(self) -> Self::IntoIter {
+     (self.gen)()
+  }
+}
+
+impl<'a, T: 'a + Clone> Monad for ListM<'a, T> {
+  fn pure(x: T) -> Self {
+     ListM::pure( x)
+  }
+}
+
+impl<'a, T: 'a + Clone> MZero for ListM<'a, T> {
+  fn mzero() -> Self {
+     ListM::mzero()
+  }
+}
+
+impl<'a, T: 'a + Clone> MPlus for ListM<'a, T> {
+  fn mplus(&mut self, other: &mut Self) {
+     *self = self.clone().mplus( other.clone());
+  }
+}
+
+//--------------------------------------------
+
+/// type constructor marker of `ListM<'a, _>` for the traits in module hkt
+pub struct ListMK;
+
+impl<'a> Kind<'a> for ListMK {
+    type Of<A: 'a> = ListM<'a, A>;
+}
+
+impl<'a, A: 'a> Kinded<'a> for ListM<'a, A> {
+    type K = ListMK;
+    type Elem = A;
+
+    fn into_kind(self) -> ListM<'a, A> { self }
+}
+
+impl<'a> Functor<'a> for ListMK {
+
+    fn fmap<A: 'a, B: 'a, F>(fa: ListM<'a, A>, f: F) -> ListM<'a, B>
+       where F: 'a + Fn(A) -> B
+    {
+       fa.bind( move |x| iter::once( f( x)))
+    }
+}
+
+impl<'a> Applicative<'a> for ListMK {
+
+    fn of<A: 'a + Clone>(x: A) -> ListM<'a, A> {
+       ListM::pure( x)
+    }
+
+    fn lift_a2<A: 'a + Clone, B: 'a + Clone, C: 'a, F>(fa: ListM<'a, A>, fb: ListM<'a, B>, f: F) -> ListM<'a, C>
+       where F: 'a + Fn(A, B) -> C
+    {
+       let f = Rc::new( f);
+       fa.bind( move |a| {
+            let f = f.clone();
+            fb.iter().map( move |b| f( a.clone(), b))
+       })
+    }
+}
+
+impl<'a> MonadK<'a> for ListMK {
+
+    fn flat_map<A: 'a + Clone, B: 'a, F>(fa: ListM<'a, A>, f: F) -> ListM<'a, B>
+       where F: 'a + Fn(A) -> ListM<'a, B>
+    {
+       fa.bind( f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mdo;
+    use crate::list::ListM;
+    use crate::monad::{Bind, Monad, MPlus};
+    use crate::hkt::lift_a2;
+    use crate::control::sequence;
+
+    struct Puzzle<'a> {
+        moves: ListM<'a, (i32, i32)>,
+    }
+
+    fn knight_moves<'a>() -> ListM<'a, (i32, i32)> {
+        mdo!{
+            dx <- ListM::from( vec![-2, -1, 1, 2]);
+            dy <- vec![-2, -1, 1, 2];
+            guard i32::abs( dx) != i32::abs( dy);
+            pure (dx, dy)
+        }
+    }
+
+    #[test]
+    fn test_list_monad() {
+        let puzzle = Puzzle { moves: knight_moves()};
+        let shared = puzzle.moves.clone();
+        assert_eq!( puzzle.moves.to_vec().len(), 8);
+        assert_eq!( (&shared).into_iter().count(), 8);
+        assert_eq!( shared.into_iter().next(), Some((-2, -1)));
+
+        let mut xs = ListM::new( || 1..3);
+        MPlus::mplus( &mut xs, &mut ListM::pure( 9));
+        assert_eq!( xs.to_vec(), vec![1, 2, 9]);
+
+        let naturals = ListM::new( || 0..);
+        assert_eq!( naturals.iter().take( 3).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!( naturals.bind( |x| vec![x; 2]).iter().take( 3).collect::<Vec<_>>(), vec![0, 0, 1]);
+
+        let sums = lift_a2( ListM::from( vec![1, 2]), ListM::from( vec![10, 20]), |a, b| a + b);
+        assert_eq!( sums.to_vec(), vec![11, 21, 12, 22]);
+        assert_eq!( sequence( vec![ListM::from( vec![1, 2]), ListM::pure( 3)]).to_vec(), vec![vec![1, 3], vec![2, 3]]);
+    }
+}
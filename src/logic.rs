@@ -0,0 +1,254 @@
+//! LogicT style fair disjunction and conjunction for iterables, with the macro "fairdo"
+//!
+//! `mdo` desugars to `flat_map`, a depth first search that never leaves the first branch
+//! of an infinite source. The trait `MonadLogic` adds to every IntoIterator:
+//!
+//! * `interleave` a fair disjunction, alternating the results of both sides
+//! * `fair_bind` the fair conjunction (Haskell's `>>-`), drawing the results of every branch in turn
+//! * `once` to keep only the first result
+//! * `ifte` a soft cut, running the `then` continuation over the condition results, or the `else` branch if there are none
+//!
+//! The macro `fairdo` has the syntax of `mdo` with every step bound through `fair_bind`,
+//! so a search over unbounded domains eventually produces every solution.
+//! A branch that runs forever without yielding, as an infinite source filtered to nothing, still blocks the search,
+//! as the infinite failure of LogicT in Haskell.
+//!
+//! ```
+//! # #[macro_use] extern crate monadic;
+//! use monadic::{fairdo, monad::Monad, logic::MonadLogic};
+//!
+//! # fn main() {
+//! // pairs of two infinite generators
+//! let pairs = fairdo!{
+//!     x <- 1..;
+//!     y <- 1..;
+//!     guard x != y;
+//!     pure (x, y)
+//! }.take(20).collect::<Vec<(u32, u32)>>();
+//!
+//! assert!( pairs.contains( &(1, 2)) && pairs.contains( &(3, 1)));
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::iter::{IntoIterator, Iterator, FlatMap, Peekable, Take};
+
+/// Fair disjunction iterator, alternating the results of two iterators until both are exhausted
+pub struct Interleave<I, J> {
+    first: I,
+    second: J,
+    from_second: bool,
+}
+
+impl<I, J> Iterator for Interleave<I, J>
+  where
+    I: Iterator,
+    J: Iterator<Item = I::Item>,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        self.from_second = !self.from_second;
+        if self.from_second {
+            self.first.next().or_else( || self.second.next())
+        } else {
+            self.second.next().or_else( || self.first.next())
+        }
+    }
+}
+
+/// Fair conjunction iterator (Haskell's `>>-`)
+///
+/// Each step opens the branch of the next source item, then draws one result from the branches in turn,
+/// so every branch is visited infinitely often even if the source and the branches are infinite.
+pub struct FairBind<I, U, F>
+  where
+    U: IntoIterator,
+{
+    source: Option<I>,
+    branches: VecDeque<U::IntoIter>,
+    f: F,
+}
+
+impl<I, U, F> Iterator for FairBind<I, U, F>
+  where
+    I: Iterator,
+    U: IntoIterator,
+    F: Fn(I::Item) -> U,
+{
+    type Item = U::Item;
+
+    fn next(&mut self) -> Option<U::Item> {
+        if let Some(x) = self.source.as_mut().and_then( |src| src.next()) {
+            self.branches.push_back( (self.f)( x).into_iter());
+        } else {
+            self.source = None;
+        }
+        loop {
+            match self.branches.pop_front() {
+                Some(mut branch) =>
+                    if let Some(y) = branch.next() {
+                        self.branches.push_back( branch);
+                        return Some(y);
+                    },
+                None => {
+                    // open the next branch, if any
+                    let x = self.source.as_mut()?.next();
+                    match x {
+                        Some(x) => self.branches.push_back( (self.f)( x).into_iter()),
+                        None => self.source = None,
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// Soft cut iterator, result of `ifte`
+pub enum Ifte<T, E> {
+    Then(T),
+    Else(E),
+}
+
+impl<T, E> Iterator for Ifte<T, E>
+  where
+    T: Iterator,
+    E: Iterator<Item = T::Item>,
+{
+    type Item = T::Item;
+
+    fn next(&mut self) -> Option<T::Item> {
+        match self {
+            Ifte::Then(it) => it.next(),
+            Ifte::Else(it) => it.next(),
+        }
+    }
+}
+
+/// the `then` branch of `ifte`, binding the peeked condition results
+pub type IfteThen<I, U, F> = FlatMap<Peekable<I>, U, F>;
+
+/// LogicT style combinators as supertrait of `IntoIterator`
+pub trait MonadLogic: IntoIterator {
+
+     /// fair disjunction (Haskell's `interleave`)
+     fn interleave<J>(self, other: J) -> Interleave<Self::IntoIter, J::IntoIter>
+        where
+          J: IntoIterator<Item = Self::Item>,
+          Self: Sized {
+        Interleave { first: self.into_iter(), second: other.into_iter(), from_second: false}
+     }
+
+     /// fair conjunction (Haskell's `>>-`)
+     fn fair_bind<U, F>(self, f: F) -> FairBind<Self::IntoIter, U, F>
+        where
+          F: Fn(Self::Item) -> U,
+          U: IntoIterator,
+          Self: Sized {
+        FairBind { source: Some(self.into_iter()), branches: VecDeque::new(), f}
+     }
+
+     /// keeps the first result only, pruning the search
+     fn once(self) -> Take<Self::IntoIter>
+        where Self: Sized {
+        self.into_iter().take( 1)
+     }
+
+     /// soft cut: binds `then` to the results of self if there is any, otherwise runs `otherwise`;
+     ///
+     /// The first result of self is evaluated when called.
+     fn ifte<U, F, E>(self, then: F, otherwise: E) -> Ifte<IfteThen<Self::IntoIter, U, F>, E::IntoIter>
+        where
+          F: Fn(Self::Item) -> U,
+          U: IntoIterator,
+          E: IntoIterator<Item = U::Item>,
+          Self: Sized {
+        let mut it = self.into_iter().peekable();
+        if it.peek().is_some() {
+            Ifte::Then( it.flat_map( then))
+        } else {
+            Ifte::Else( otherwise.into_iter())
+        }
+     }
+}
+
+impl<R> MonadLogic for R where R: IntoIterator {}
+
+/// macro for iterables with fair conjunction, same syntax as `mdo` binding through `MonadLogic::fair_bind`
+///
+/// You must import the traits Monad and MonadLogic.
+///
+/// You can use:
+/// * `pure return_expresion`    to return an expression value
+/// * `monadic_expression`       to end with a monad expression
+/// * `v <- pure return_expresion`  to lift a rhs expression value with Option::pure(x)
+/// * `v <- monadic_expression`  to use the monad result
+/// * `&v <- &container`  to use a reference item result from a by reference container
+/// * `_ <- monadic_expression`  to ignore the monad result
+/// * `let z = expression`       to combine monad results
+/// * `guard boolean_expression` to filter results
+/// * `pattern <- monadic_expression`  to destructure the monad result, dropping the results that don't match
+/// * `let pattern = expression`  to destructure an expression, dropping the branch if it doesn't match
+///
+#[macro_export]
+macro_rules! fairdo {
+  (@bind [$($p:tt)*] $monad:expr ; $($rest:tt)*) => [($monad).fair_bind( move |x| {
+                                                            #[allow(unreachable_patterns)]
+                                                            match x { $($p)* => Some( fairdo!($($rest)*)), _ => None }.into_iter().flatten()
+                                                        } )];
+
+  (pure $e:expr                           ) => [Option::pure($e)];
+  (let $v:ident = $e:expr ; $($rest:tt)*) => [Option::pure($e).fair_bind( move |$v| { fairdo!($($rest)*)} )];
+  (let $p:pat = $e:expr ; $($rest:tt)*) => [Option::pure($e).fair_bind( move |x| {
+                                                #[allow(unreachable_patterns)]
+                                                match x { $p => Some( fairdo!($($rest)*)), _ => None }.into_iter().flatten()
+                                            } )];
+  (guard $boolean:expr ; $($rest:tt)*) => [(if $boolean {Some(())} else {None}).fair_bind( move |_| { fairdo!($($rest)*)} )];
+  (_ <- $monad:expr ; $($rest:tt)* ) => [($monad).fair_bind( move |_| { fairdo!($($rest)*)} )];
+  (&$v:ident <- $monad:expr ; $($rest:tt)* ) => [($monad).fair_bind( move |&$v| { fairdo!($($rest)*)} )];
+  ($v:ident <- pure $e:expr ; $($rest:tt)* ) => [Option::pure($e).fair_bind( move |$v| { fairdo!($($rest)*)} )];
+  ($v:ident <- $monad:expr ; $($rest:tt)* ) => [($monad).fair_bind( move |$v| { fairdo!($($rest)*)} )];
+  ($($tokens:tt)+                         ) => [$crate::split_pat_bind!(fairdo $($tokens)+)];
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::monad::Monad;
+    use crate::logic::MonadLogic;
+
+    #[test]
+    fn test_interleave_and_fair_bind() {
+        let xs = (1..4).interleave( vec![10, 20]).collect::<Vec<_>>();
+        assert_eq!( xs, vec![1, 10, 2, 20, 3]);
+
+        let odds_evens = (0..).map( |x| 2 * x + 1).interleave( (0..).map( |x| 2 * x)).take( 6).collect::<Vec<u32>>();
+        assert_eq!( odds_evens, vec![1, 0, 3, 2, 5, 4]);
+
+        // finite sources keep every result
+        let mut ys = vec![1, 2, 3].fair_bind( |x| vec![x; x]).collect::<Vec<_>>();
+        ys.sort();
+        assert_eq!( ys, vec![1, 2, 2, 3, 3, 3]);
+
+        // infinite sources with infinite branches
+        let pairs = fairdo!{
+            x <- 1..;
+            y <- 1..;
+            pure (x, y)
+        }.take( 50).collect::<Vec<(u32, u32)>>();
+        assert!( pairs.contains( &(3, 1)) && pairs.contains( &(1, 3)) && pairs.contains( &(2, 2)));
+    }
+
+    #[test]
+    fn test_once_and_ifte() {
+        assert_eq!( (5..).once().collect::<Vec<_>>(), vec![5]);
+
+        let small_divisor = |n: u32| (2..n).filter( move |d| n % d == 0).once();
+
+        let primes = fairdo!{
+            n <- 2..;
+            p <- small_divisor( n).ifte( |_| None, Some(n));
+            pure p
+        }.take( 5).collect::<Vec<_>>();
+        assert_eq!( primes, vec![2, 3, 5, 7, 11]);
+    }
+}
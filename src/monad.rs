@@ -1,7 +1,10 @@
 //! definition of Bind and Monad traits based monadic macro 
 
-use std::iter::{IntoIterator, Iterator, FlatMap};
-use std::collections::{LinkedList, VecDeque};
+use std::iter::{IntoIterator, Iterator, FlatMap, Peekable};
+use std::collections::{LinkedList, VecDeque, HashSet, BTreeSet, BinaryHeap, HashMap, BTreeMap};
+use std::hash::Hash;
+use std::iter::FromIterator;
+use std::mem;
 
 /// `Bind` as supertrait of `IntoIterator`
 ///
@@ -101,8 +104,33 @@ impl<T> MZero for VecDeque<T> {
 
 pub trait MPlus: MZero { 
    fn mplus(&mut self, _: &mut Self) ;
+   
+   /// `mplus` with a lazily computed second operand, as Haskell's `<|>`;
+   /// first-success instances (Option, Result) don't compute it when self succeeds
+   fn mplus_with<F>(mut self, f: F) -> Self
+     where 
+       F: FnOnce() -> Self,
+       Self: Sized {
+     let mut other = f();
+     self.mplus( &mut other);
+     self
+   }
 }
 
+/// first success: keeps self if it is `Some`, otherwise takes the other
+impl<T> MPlus for Option<T> {
+   fn mplus(&mut self, other: &mut Self) {
+      if self.is_none() {
+         *self = other.take();
+      }
+   }
+   
+   fn mplus_with<F: FnOnce() -> Self>(self, f: F) -> Self {
+      self.or_else( f)
+   }
+}
+
+
 impl<T> MPlus for Vec<T> {
    fn mplus(&mut self, other: &mut Self) {
       self.append( other);
@@ -121,6 +149,314 @@ impl<T> MPlus for VecDeque<T> {
    }
 }
 
+/// choice between two alternatives without an empty one (PureScript's `Alt`), the `<|>` of the macro `alt`
+///
+/// Every MPlus is an Alt through `mplus_with`. `Result` is an Alt but not an MPlus,
+/// as its empty value would need an error without a cause; it keeps the first `Ok`, or else the last error.
+pub trait Alt: Monad {
+   /// `self <|> f()`, computing the second operand only when needed
+   fn alt_with<F: FnOnce() -> Self>(self, f: F) -> Self;
+}
+
+impl<M: MPlus> Alt for M {
+   fn alt_with<F: FnOnce() -> Self>(self, f: F) -> Self {
+      self.mplus_with( f)
+   }
+}
+
+impl<T, E> Alt for Result<T, E> {
+   fn alt_with<F: FnOnce() -> Self>(self, f: F) -> Self {
+      self.or_else( |_| f())
+   }
+}
+
+//--------------------------------------------
+// sets, maps, heaps and boxed slices
+
+impl<T: Eq + Hash> Monad for HashSet<T> {
+   fn pure(x: T) -> Self {
+      let mut s = Self::new();
+      s.insert( x);
+      s
+   }
+}
+
+impl<T: Eq + Hash> MZero for HashSet<T> {
+
+   fn mzero() -> Self {Self::new()}
+}
+
+/// union
+impl<T: Eq + Hash> MPlus for HashSet<T> {
+   fn mplus(&mut self, other: &mut Self) {
+      self.extend( other.drain());
+   }
+}
+
+impl<T: Ord> Monad for BTreeSet<T> {
+   fn pure(x: T) -> Self {
+      let mut s = Self::new();
+      s.insert( x);
+      s
+   }
+}
+
+impl<T: Ord> MZero for BTreeSet<T> {
+
+   fn mzero() -> Self {Self::new()}
+}
+
+/// union
+impl<T: Ord> MPlus for BTreeSet<T> {
+   fn mplus(&mut self, other: &mut Self) {
+      self.append( other);
+   }
+}
+
+impl<T: Ord> Monad for BinaryHeap<T> {
+   fn pure(x: T) -> Self {
+      let mut h = Self::new();
+      h.push( x);
+      h
+   }
+}
+
+impl<T: Ord> MZero for BinaryHeap<T> {
+
+   fn mzero() -> Self {Self::new()}
+}
+
+impl<T: Ord> MPlus for BinaryHeap<T> {
+   fn mplus(&mut self, other: &mut Self) {
+      self.append( other);
+   }
+}
+
+/// a map as a monad of its `(key, value)` entries
+impl<K: Eq + Hash, V> Monad for HashMap<K, V> {
+   fn pure((k, v): (K, V)) -> Self {
+      let mut m = Self::new();
+      m.insert( k, v);
+      m
+   }
+}
+
+impl<K: Eq + Hash, V> MZero for HashMap<K, V> {
+
+   fn mzero() -> Self {Self::new()}
+}
+
+/// left-biased union: the entries of self win on common keys
+impl<K: Eq + Hash, V> MPlus for HashMap<K, V> {
+   fn mplus(&mut self, other: &mut Self) {
+      for (k, v) in other.drain() {
+         self.entry( k).or_insert( v);
+      }
+   }
+}
+
+/// a map as a monad of its `(key, value)` entries
+impl<K: Ord, V> Monad for BTreeMap<K, V> {
+   fn pure((k, v): (K, V)) -> Self {
+      let mut m = Self::new();
+      m.insert( k, v);
+      m
+   }
+}
+
+impl<K: Ord, V> MZero for BTreeMap<K, V> {
+
+   fn mzero() -> Self {Self::new()}
+}
+
+/// left-biased union: the entries of self win on common keys
+impl<K: Ord, V> MPlus for BTreeMap<K, V> {
+   fn mplus(&mut self, other: &mut Self) {
+      for (k, v) in mem::take( other) {
+         self.entry( k).or_insert( v);
+      }
+   }
+}
+
+/// `Box<T>` is not an iterable, but the boxed slice `Box<[T]>` is
+impl<T> Monad for Box<[T]> {
+   fn pure(x: T) -> Self {
+      Box::new( [x])
+   }
+}
+
+impl<T> MZero for Box<[T]> {
+
+   fn mzero() -> Self {Box::new( [])}
+}
+
+impl<T> MPlus for Box<[T]> {
+   fn mplus(&mut self, other: &mut Self) {
+      let mut v = mem::take( self).into_vec();
+      v.extend( mem::take( other).into_vec());
+      *self = v.into_boxed_slice();
+   }
+}
+
+/// `bind` collecting into a set of the same kind as self, for the set monad macro `setdo`
+pub trait SetBind: IntoIterator + Sized {
+   type Set<U>: IntoIterator<Item = U>;
+
+   fn bind_set<U, F>(self, f: F) -> Self::Set<U::Item>
+      where 
+        F: Fn(Self::Item) -> U,
+        U: IntoIterator,
+        Self::Set<U::Item>: FromIterator<U::Item>,
+   {
+      self.into_iter().flat_map( f).collect()
+   }
+}
+
+impl<T> SetBind for HashSet<T> {
+   type Set<U> = HashSet<U>;
+}
+
+impl<T> SetBind for BTreeSet<T> {
+   type Set<U> = BTreeSet<U>;
+}
+
+/// combines the values with `mplus`, starting from `mzero`
+///
+/// The empty list of alternatives gives `mzero`, so it is not defined for `Result`, which has none;
+/// fold a non empty list of Results with `Alt::alt_with` instead.
+pub fn msum<M, I>(ms: I) -> M 
+  where 
+    M: MPlus,
+    I: IntoIterator<Item = M>,
+{
+   ms.into_iter().fold( M::mzero(), |mut acc, mut m| { acc.mplus( &mut m); acc })
+}
+
+/// combines lazily computed alternatives with `mplus_with`, starting from `mzero`;
+/// for Option, the alternatives after the first success are not computed
+pub fn asum<M, I, F>(alternatives: I) -> M 
+  where 
+    M: MPlus,
+    I: IntoIterator<Item = F>,
+    F: FnOnce() -> M,
+{
+   alternatives.into_iter().fold( M::mzero(), |acc, f| acc.mplus_with( f))
+}
+
+/// keeps the results satisfying the predicate; the others become `mzero`
+pub fn mfilter<M, P>(p: P, m: M) -> M
+  where 
+    M: MPlus,
+    P: Fn(&M::Item) -> bool,
+{
+   msum( m.into_iter().filter( p).map( M::pure))
+}
+
+/// a step of a `tail_rec_m` loop: `Loop` to go on with a new argument, `Done` with the result
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step<A, B> {
+   Loop(A),
+   Done(B),
+}
+
+/// iterator of the results of `tail_rec_m`, keeping the pending branches in a heap allocated stack
+pub struct TailRecM<M: IntoIterator, F> {
+   stack: Vec<Peekable<M::IntoIter>>,
+   f: F,
+}
+
+impl<A, B, M, F> Iterator for TailRecM<M, F>
+  where
+    M: IntoIterator<Item = Step<A, B>>,
+    F: Fn(A) -> M,
+{
+   type Item = B;
+   
+   fn next(&mut self) -> Option<B> {
+      loop {
+         let mut branch = self.stack.pop()?;
+         if let Some(step) = branch.next() {
+            // an exhausted branch is dropped before its continuation runs, so a linear loop keeps one branch
+            if branch.peek().is_some() {
+               self.stack.push( branch);
+            }
+            match step {
+               Step::Loop(a) => self.stack.push( (self.f)( a).into_iter().peekable()),
+               Step::Done(b) => return Some(b),
+            }
+         }
+      }
+   }
+}
+
+/// stack safe monadic loop over iterables (PureScript's `tailRecM`), 
+/// applying `f` to each `Loop` argument until it returns `Done`, in depth first order as `mdo`
+///
+/// It is lazy, and runs in constant stack whatever the number of iterations.
+///
+/// A `Result` iterates its `Ok` value only, so an `Err` step silently ends the loop; use `result_tail_rec_m` to get the `Err`.
+pub fn tail_rec_m<A, B, M, F>(a: A, f: F) -> TailRecM<M, F>
+  where
+    M: IntoIterator<Item = Step<A, B>>,
+    F: Fn(A) -> M,
+{
+   let first = f( a).into_iter().peekable();
+   TailRecM { stack: vec![first], f}
+}
+
+/// stack safe monadic loop over `Result`, applying `f` to each `Loop` argument until it returns `Done`,
+/// or returning the first `Err` unchanged
+pub fn result_tail_rec_m<A, B, E, F>(a: A, f: F) -> Result<B, E>
+  where
+    F: Fn(A) -> Result<Step<A, B>, E>,
+{
+   let mut step = f( a)?;
+   loop {
+      match step {
+         Step::Loop(a1) => step = f( a1)?,
+         Step::Done(b) => return Ok(b),
+      }
+   }
+}
+
+/// macro for a choice between alternatives, `a <|> b <|> pure c`, as `Alt::alt_with` chained: `a.alt_with( || b).alt_with( || Monad::pure( c))`
+///
+/// The alternatives after the first success of Option and Result are not evaluated.
+/// It is also available in parenthesis on the right of `<-` in `mdo`, `optdo` and `resdo`, as `v <- (a <|> b);`.
+///
+/// Each alternative must be an expression or `pure expression`; as the alternatives are split at the tokens `<|>`,
+/// an expression containing them must be parenthesized.
+#[macro_export]
+macro_rules! alt {
+  // splits the alternatives at `<|>`
+  (@split [$($alts:tt)*] [$($cur:tt)*] < | > $($rest:tt)*) => [$crate::alt!(@split [$($alts)* [$($cur)*]] [] $($rest)*)];
+  (@split [$($alts:tt)*] [$($cur:tt)*] $t:tt $($rest:tt)*) => [$crate::alt!(@split [$($alts)*] [$($cur)* $t] $($rest)*)];
+  (@split [[$($first:tt)*] $($alts:tt)*] [$($cur:tt)*]) => [$crate::alt!(@fold ($crate::alt!(@one $($first)*)) $($alts)* [$($cur)*])];
+  (@split [] [$($cur:tt)*]) => [$crate::alt!(@one $($cur)*)];
+  
+  (@fold ($acc:expr)) => [$acc];
+  (@fold ($acc:expr) [$($next:tt)*] $($rest:tt)*) => [$crate::alt!(@fold ($crate::monad::Alt::alt_with( $acc, || $crate::alt!(@one $($next)*))) $($rest)*)];
+  
+  (@one pure $e:expr) => [$crate::monad::Monad::pure( $e)];
+  (@one $e:expr) => [$e];
+  
+  ($($tokens:tt)+) => [$crate::alt!(@split [] [] $($tokens)+)];
+}
+
+/// internal macro of `mdo`, `fairdo` and `bfsdo`, splitting a refutable pattern bind `pattern <- monad_expression ; rest`
+///
+/// The token trees before `<-`, at most 16 of them, are the pattern, passed back as `$mac!(@bind [pattern] monad_expression ; rest)`.
+/// Without a `<-` in reach, the tokens are emitted unchanged as the ending expression of the block.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! split_pat_bind {
+  (@acc $mac:ident [$($p:tt)*] [$($budget:tt)*] <- $($rest:tt)+) => [$crate::$mac!(@bind [$($p)*] $($rest)+)];
+  (@acc $mac:ident [$($p:tt)*] [$b:tt $($budget:tt)*] $t:tt $($rest:tt)*) => [$crate::split_pat_bind!(@acc $mac [$($p)* $t] [$($budget)*] $($rest)*)];
+  (@acc $mac:ident [$($p:tt)*] [$($budget:tt)*] $($rest:tt)*) => [$($p)* $($rest)*];
+  
+  ($mac:ident $($tokens:tt)+) => [$crate::split_pat_bind!(@acc $mac [] [_ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _] $($tokens)+)];
+}
+
 /// macro for iterables (IntoIterator) as monads enabling monad comprehensions over iterables
 ///
 /// You can use: 
@@ -132,23 +468,185 @@ impl<T> MPlus for VecDeque<T> {
 /// * `_ <- monadic_expression`  to ignore the monad result
 /// * `let z = expression`       to combine monad results
 /// * `guard boolean_expression` to filter results
+/// * `pattern <- monadic_expression`  to destructure the monad result, dropping the results that don't match
+/// * `let pattern = expression`  to destructure an expression, dropping the branch if it doesn't match
+/// * `v1 <- monadic_expr1, v2 <- monadic_expr2 ;`  to bind zipped sources advancing in lockstep (parallel comprehension)
+/// * `v <- (monadic_expr1 <|> monadic_expr2 <|> pure expression) ;`  to bind the choice of the macro `alt`
+///
+/// A refutable pattern that fails to match ends the branch with no results, as `fail` in Haskell's list monad.
+/// The pattern before `<-` takes at most 16 token trees, a parenthesized or braced group counting as one.
+///
+/// The zipped bindings take identifiers, tuple patterns or `_`, and stop at the shortest source.
 ///
 #[macro_export]
 macro_rules! mdo {
+  // parallel binds: the sources are zipped, nesting the patterns as ((v1, v2), v3)
+  (@zip ($($p:tt)*) ($zipped:expr) $v:tt <- $monad:expr , $($rest:tt)*) => [mdo!(@zip (($($p)*, $v)) (($zipped).into_iter().zip( $monad)) $($rest)*)];
+  (@zip ($($p:tt)*) ($zipped:expr) $v:tt <- $monad:expr ; $($rest:tt)*) => [($zipped).into_iter().zip( $monad).bind( move |($($p)*, $v)| { mdo!($($rest)*)} )];
+  
+  // pattern binds, split by `split_pat_bind`
+  (@bind [$($p:tt)*] ( $($alt:tt)+ ) ; $($rest:tt)*) => [mdo!(@bind [$($p)*] $crate::alt!($($alt)+) ; $($rest)*)];
+  (@bind [$($p:tt)*] $monad:expr ; $($rest:tt)*) => [($monad).bind( move |x| { 
+                                                            #[allow(unreachable_patterns)]
+                                                            match x { $($p)* => Some( mdo!($($rest)*)), _ => None }.into_iter().flatten()
+                                                        } )];
+  
   (pure $e:expr                           ) => [Option::pure($e)];
   (let $v:ident = $e:expr ; $($rest:tt)*) => [Option::pure($e).bind( move |$v| { mdo!($($rest)*)} )];
+  (let $p:pat = $e:expr ; $($rest:tt)*) => [Option::pure($e).bind( move |x| { 
+                                                #[allow(unreachable_patterns)]
+                                                match x { $p => Some( mdo!($($rest)*)), _ => None }.into_iter().flatten()
+                                            } )];
   (guard $boolean:expr ; $($rest:tt)*) => [(if $boolean {Some(())} else {None}).bind( move |_| { mdo!($($rest)*)} )];
+  (_ <- ( $($alt:tt)+ ) ; $($rest:tt)* ) => [$crate::alt!($($alt)+).bind( move |_| { mdo!($($rest)*)} )];
+  ($v:ident <- ( $($alt:tt)+ ) ; $($rest:tt)* ) => [$crate::alt!($($alt)+).bind( move |$v| { mdo!($($rest)*)} )];
   (_ <- $monad:expr ; $($rest:tt)* ) => [($monad).bind( move |_| { mdo!($($rest)*)} )];
   (&$v:ident <- $monad:expr ; $($rest:tt)* ) => [($monad).bind( move |&$v| { mdo!($($rest)*)} )];
   ($v:ident <- pure $e:expr ; $($rest:tt)* ) => [Option::pure($e).bind( move |$v| { mdo!($($rest)*)} )];
+  ($v:tt <- $monad:expr , $($rest:tt)* ) => [mdo!(@zip ($v) ($monad) $($rest)*)];
   ($v:ident <- $monad:expr ; $($rest:tt)* ) => [($monad).bind( move |$v| { mdo!($($rest)*)} )];
+  ($($tokens:tt)+                         ) => [$crate::split_pat_bind!(mdo $($tokens)+)];
+}
+
+/// macro for `Result<T, E>` as a short-circuiting monad, returning the first `Err` unchanged
+///
+/// Unlike `mdo`, binding goes through `Result::and_then`, so the block value is a `Result<T, E>`
+/// and all steps must share the same error type `E`.
+///
+/// You can use: 
+/// * `pure return_expresion`    to return an expression value as `Ok`
+/// * `monadic_expression`       to end with a `Result` expression
+/// * `v <- pure return_expresion`  to lift a rhs expression value with Result::pure(x)
+/// * `v <- monadic_expression`  to use the `Ok` value
+/// * `_ <- monadic_expression`  to ignore the `Ok` value
+/// * `let z = expression`       to combine monad results
+/// * `guard boolean_expression, error_expression` to fail with `Err(error_expression)` when false
+/// * `v <- (monadic_expr1 <|> monadic_expr2 <|> pure expression)`  to use the first `Ok` of the alternatives
+///
+#[macro_export]
+macro_rules! resdo {
+  (pure $e:expr                           ) => [Result::pure($e)];
+  (let $v:ident = $e:expr ; $($rest:tt)*) => [Result::pure($e).and_then( move |$v| { resdo!($($rest)*)} )];
+  (guard $boolean:expr , $err:expr ; $($rest:tt)*) => [(if $boolean {Ok(())} else {Err($err)}).and_then( move |_| { resdo!($($rest)*)} )];
+  (_ <- ( $($alt:tt)+ ) ; $($rest:tt)* ) => [$crate::alt!($($alt)+).and_then( move |_| { resdo!($($rest)*)} )];
+  ($v:ident <- ( $($alt:tt)+ ) ; $($rest:tt)* ) => [$crate::alt!($($alt)+).and_then( move |$v| { resdo!($($rest)*)} )];
+  (_ <- $monad:expr ; $($rest:tt)* ) => [($monad).and_then( move |_| { resdo!($($rest)*)} )];
+  ($v:ident <- pure $e:expr ; $($rest:tt)* ) => [Result::pure($e).and_then( move |$v| { resdo!($($rest)*)} )];
+  ($v:ident <- $monad:expr ; $($rest:tt)* ) => [($monad).and_then( move |$v| { resdo!($($rest)*)} )];
   ($monad:expr                            ) => [$monad];
 }
 
+/// macro for `Option<T>` as a monad, returning an `Option<T>` instead of a `FlatMap` iterator
+///
+/// Binding goes through `Option::and_then`, `pure` uses `Option::pure` and `guard` maps to `Option::mzero()`,
+/// so you must import the traits Monad and MZero.
+///
+/// You can use: 
+/// * `pure return_expresion`    to return an expression value as `Some`
+/// * `monadic_expression`       to end with an `Option` expression
+/// * `v <- pure return_expresion`  to lift a rhs expression value with Option::pure(x)
+/// * `v <- monadic_expression`  to use the `Some` value
+/// * `&v <- monadic_expression`  to deref an `Option<&T>` value
+/// * `_ <- monadic_expression`  to ignore the `Some` value
+/// * `let z = expression`       to combine monad results
+/// * `guard boolean_expression` to return `None` when false
+/// * `v <- (monadic_expr1 <|> monadic_expr2 <|> pure expression)`  to use the first `Some` of the alternatives
+///
+#[macro_export]
+macro_rules! optdo {
+  (pure $e:expr                           ) => [Option::pure($e)];
+  (let $v:ident = $e:expr ; $($rest:tt)*) => [Option::pure($e).and_then( move |$v| { optdo!($($rest)*)} )];
+  (guard $boolean:expr ; $($rest:tt)*) => [(if $boolean {Option::pure(())} else {Option::mzero()}).and_then( move |_| { optdo!($($rest)*)} )];
+  (_ <- ( $($alt:tt)+ ) ; $($rest:tt)* ) => [$crate::alt!($($alt)+).and_then( move |_| { optdo!($($rest)*)} )];
+  ($v:ident <- ( $($alt:tt)+ ) ; $($rest:tt)* ) => [$crate::alt!($($alt)+).and_then( move |$v| { optdo!($($rest)*)} )];
+  (_ <- $monad:expr ; $($rest:tt)* ) => [($monad).and_then( move |_| { optdo!($($rest)*)} )];
+  (&$v:ident <- $monad:expr ; $($rest:tt)* ) => [($monad).and_then( move |&$v| { optdo!($($rest)*)} )];
+  ($v:ident <- pure $e:expr ; $($rest:tt)* ) => [Option::pure($e).and_then( move |$v| { optdo!($($rest)*)} )];
+  ($v:ident <- $monad:expr ; $($rest:tt)* ) => [($monad).and_then( move |$v| { optdo!($($rest)*)} )];
+  ($monad:expr                            ) => [$monad];
+}
+
+/// macro for the set monad over `HashSet` or `BTreeSet`, pruning duplicate branches at each step
+///
+/// Two branches with the same values of the variables in scope run the same rest of the block,
+/// so the macro keeps the rows of the variables in scope in a set of the kind of the first source, 
+/// collected after each step through `SetBind::bind_set`, and the result is a set of the same kind.
+/// The variables must be `Clone` and `Eq + Hash` (HashSet) or `Ord` (BTreeSet).
+///
+/// The first source must be a set; the next ones, any iterable, are evaluated per row inside `move` closures,
+/// so bind a reference to an outer collection (`let ys = &ys;`) before using it there.
+///
+/// You can use: 
+/// * `pure return_expresion`    to return an expression value
+/// * `iterable_expression`      to end with an iterable
+/// * `v <- iterable_expression` to use each item
+/// * `let v = expression`       to combine the results
+/// * `let (v1, v2) = expression` to destructure a tuple into variables
+/// * `guard boolean_expression` to filter results
+///
+#[macro_export]
+macro_rules! setdo {
+  (@rows [$($vs:ident)*] ($rows:expr) $($step:tt)+) => [
+        $crate::row_step!(setdo $crate::monad::SetBind::bind_set; [$($vs)*] ($rows) $($step)+)
+  ];
+  
+  ($v:ident <- $monad:expr ; $($rest:tt)*) => [
+        setdo!(@rows [$v] ($crate::monad::SetBind::bind_set( $monad, |$v| Some(($v,)))) $($rest)*)
+  ];
+}
+
+/// internal macro of `setdo` and `qdo`: a step over the rows of the variables in scope `[$($vs)*]`
+///
+/// `row_step!(mac bind_fn; [vs] (rows) step ; rest)` binds each row with `bind_fn` and goes on with `mac!(@rows [vs] (new_rows) rest)`,
+/// or ends the block with a `pure` or iterable expression.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! row_step {
+  ($mac:ident $bind:path; [$($vs:ident)*] ($rows:expr) pure $e:expr) => [
+        $bind( $rows, move |row| { $crate::row_step!(@unpack [$($vs)*] row); Some($e) })
+  ];
+  ($mac:ident $bind:path; [$($vs:ident)*] ($rows:expr) let ($($v:ident),+ $(,)?) = $e:expr ; $($rest:tt)*) => [
+        $crate::$mac!(@rows [$($vs)* $($v)+] ($bind( $rows, move |row| {
+                                                 $crate::row_step!(@unpack [$($vs)*] row);
+                                                 let ($($v,)+) = $e;
+                                                 Some(($($vs,)* $($v,)+))
+                                             })) $($rest)*)
+  ];
+  ($mac:ident $bind:path; [$($vs:ident)*] ($rows:expr) let $v:ident $(: $t:ty)? = $e:expr ; $($rest:tt)*) => [
+        $crate::$mac!(@rows [$($vs)* $v] ($bind( $rows, move |row| {
+                                             $crate::row_step!(@unpack [$($vs)*] row);
+                                             let $v $(: $t)? = $e;
+                                             Some(($($vs,)* $v,))
+                                         })) $($rest)*)
+  ];
+  ($mac:ident $bind:path; [$($vs:ident)*] ($rows:expr) guard $boolean:expr ; $($rest:tt)*) => [
+        $crate::$mac!(@rows [$($vs)*] ($bind( $rows, move |row| {
+                                          $crate::row_step!(@unpack [$($vs)*] row);
+                                          if $boolean {Some(($($vs,)*))} else {None}
+                                      })) $($rest)*)
+  ];
+  ($mac:ident $bind:path; [$($vs:ident)*] ($rows:expr) $v:ident <- $monad:expr ; $($rest:tt)*) => [
+        $crate::$mac!(@rows [$($vs)* $v] ($bind( $rows, move |row| {
+                                             $crate::row_step!(@unpack [$($vs)*] row);
+                                             ::std::iter::IntoIterator::into_iter( $monad).map( move |$v| ($($vs.clone(),)* $v,))
+                                         })) $($rest)*)
+  ];
+  ($mac:ident $bind:path; [$($vs:ident)*] ($rows:expr) $monad:expr) => [
+        $bind( $rows, move |row| { $crate::row_step!(@unpack [$($vs)*] row); $monad })
+  ];
+  
+  // binds the variables in scope, some of which may be unused by the step
+  (@unpack [$($vs:ident)*] $row:ident) => [
+        #[allow(unused_variables)]
+        let ($($vs,)*) = $row;
+  ];
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::monad::{Bind, Monad};
+    use crate::monad::{Bind, Monad, MZero};
     use quickcheck::quickcheck;
+    use std::collections::HashMap;
     
     quickcheck!{
         fn prop_monad_comprehension_vs_iteration( xs: Vec<i32>) -> bool {
@@ -165,6 +663,228 @@ mod tests {
             
             ys == zs
         }
-    }    
-}
+    }
+    
+    fn parse_positive( s: &str) -> Result<i32, String> {
+        resdo!{
+            x <- s.trim().parse::<i32>().map_err( |e| e.to_string());
+            guard x > 0, format!("not positive: {}", x);
+            pure x
+        }
+    }
+    
+    #[test]
+    fn test_resdo_short_circuits_on_first_err() {
+    
+        assert_eq!( parse_positive( " 5"), Ok(5));
+        assert_eq!( parse_positive( "-5"), Err( String::from( "not positive: -5")));
+        assert!( parse_positive( "five").is_err());
+        
+        let res: Result<(i32, i32), String> = resdo!{
+            x <- parse_positive( "2");
+            y <- parse_positive( "0");
+            _ <- Err::<(), _>( String::from( "unreachable"));
+            pure (x, y)
+        };
+        assert_eq!( res, Err( String::from( "not positive: 0")));
+    }
+    
+    #[test]
+    fn test_optdo_returns_option() {
+    
+        let ages: HashMap<&str, i32> = vec![("ann", 30), ("bob", 15)].into_iter().collect();
+        let adult_age = |name: &str| optdo!{
+            &age <- ages.get( name);
+            guard age >= 18;
+            pure age
+        };
+        
+        assert_eq!( adult_age( "ann"), Some(30));
+        assert_eq!( adult_age( "bob"), None);
+        assert_eq!( adult_age( "cid"), None);
+    }
+    
+    #[derive(Clone, Copy)]
+    struct Point { x: i32, y: i32 }
+    
+    #[test]
+    fn test_mdo_refutable_patterns() {
+    
+        let pairs = mdo!{
+            (a, b) <- vec![(1, 'a'), (2, 'b')];
+            Some(n) <- vec![Some(10), None, Some(20)];
+            let [c, _] = [a + n, 0];
+            pure (c, b)
+        }.collect::<Vec<_>>();
+        assert_eq!( pairs, vec![(11, 'a'), (21, 'a'), (12, 'b'), (22, 'b')]);
+        
+        let pts = vec![Point{ x: 1, y: 2}, Point{ x: 0, y: 5}];
+        let ys = mdo!{
+            &Point { x, y } <- &pts;
+            let 1..=9 = x;
+            Ok(z) | Err(z) <- vec![Ok::<i32, i32>(y), Err(y * 10)];
+            vec![z]
+        }.collect::<Vec<_>>();
+        assert_eq!( ys, vec![2, 20]);
+        
+        let small = mdo!{
+            n @ 1..=2 <- vec![0, 1, 2, 3];
+            Some(ref s) <- vec![Some( n.to_string())];
+            pure s.clone()
+        }.collect::<Vec<_>>();
+        assert_eq!( small, vec!["1", "2"]);
+        
+        // an ending expression of more token trees than the recursion limit
+        let inc = |x: i32| x + 1;
+        let long = mdo!{
+            x <- vec![0];
+            vec![x].into_iter()
+                .map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc)
+                .map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc)
+                .map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc)
+                .map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc)
+                .map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc).map( inc)
+        }.collect::<Vec<_>>();
+        assert_eq!( long, vec![50]);
+    }
+    
+    #[test]
+    fn test_mdo_zipped_binds() {
+        let times = [10, 20, 30];
+        let readings = [1.5, 2.0, 0.5, 9.9];
+    
+        let xs = mdo!{
+            t <- times.iter(), r <- readings.iter(), (i, _) <- (0..).zip( "abc".chars());
+            guard *r > 1.0;
+            d <- vec![0, 1];
+            pure (i, t + d)
+        }.collect::<Vec<_>>();
+        assert_eq!( xs, vec![(0, 10), (0, 11), (1, 20), (1, 21)]);
+        
+        let ys = mdo!{
+            x <- 1..4, _ <- "ab".chars();
+            pure x
+        }.collect::<Vec<_>>();
+        assert_eq!( ys, vec![1, 2]);
+    }
+    
+    #[test]
+    fn test_alternatives() {
+        use crate::monad::{Alt, msum, asum, mfilter};
+        use std::cell::Cell;
+        use std::num::ParseIntError;
+        
+        let disk_reads = Cell::new( 0);
+        let cache: HashMap<&str, i32> = vec![("a", 1)].into_iter().collect();
+        let disk = |k: &str| { disk_reads.set( disk_reads.get() + 1); if k == "b" {Some(2)} else {None} };
+        
+        let lookup = |k| optdo!{
+            v <- (cache.get( k).cloned() <|> disk( k) <|> pure 0);
+            pure v * 10
+        };
+        assert_eq!( (lookup( "a"), lookup( "b"), lookup( "c")), (Some(10), Some(20), Some(0)));
+        assert_eq!( disk_reads.get(), 2);
+        
+        let parsed: Result<i32, String> = resdo!{
+            n <- ("x".parse::<i32>().map_err( |e| e.to_string()) <|> Ok(5));
+            pure n + 1
+        };
+        assert_eq!( parsed, Ok(6));
+        assert_eq!( alt!( Err::<i32, _>( String::from( "a")) <|> Err( String::from( "b"))), Err( String::from( "b")));
+        
+        let xs = mdo!{
+            x <- (vec![1, 2] <|> vec![3]);
+            Some(y) <- (vec![None] <|> vec![Some(x * 10)]);
+            pure y
+        }.collect::<Vec<_>>();
+        assert_eq!( xs, vec![10, 20, 30]);
+        
+        assert_eq!( msum( vec![None, Some(1), Some(2)]), Some(1));
+        assert_eq!( msum( vec![vec![1], vec![], vec![2, 3]]), vec![1, 2, 3]);
+        assert_eq!( asum( vec![Box::new( || None) as Box<dyn FnOnce() -> Option<i32>>, Box::new( || Some(3)), Box::new( || panic!())]), Some(3));
+        assert_eq!( mfilter( |x| x % 2 == 0, vec![1, 2, 3, 4]), vec![2, 4]);
+        assert_eq!( mfilter( |x| *x > 5, Some(3)), None);
+        
+        // Result needs no Default error, and keeps the last one
+        let r: Result<i32, ParseIntError> = Err( "x".parse::<i32>().unwrap_err()).alt_with( || "1".parse());
+        assert_eq!( r, Ok(1));
+        let last = alt!( "x".parse::<i32>() <|> "".parse::<i32>());
+        assert_eq!( last, Err( "".parse::<i32>().unwrap_err()));
+        
+        // an alternative may be any expression, containing `|` or `<` tokens, or a block
+        let n = 3;
+        assert_eq!( alt!( Some(n).filter( |&x| x < 2) <|> {let m = n * 2; Some(m)} <|> pure 0), Some(6));
+    }
+    
+    #[test]
+    fn test_set_and_map_instances() {
+        use crate::monad::{MPlus, msum};
+        use std::collections::{HashSet, BTreeSet, BinaryHeap, BTreeMap};
+        use std::cell::Cell;
+        
+        let edges: HashMap<u32, Vec<u32>> = vec![(1, vec![2, 3]), (2, vec![4]), (3, vec![4]), (4, vec![1])].into_iter().collect();
+        let edges = &edges;
+        let steps = Cell::new( 0);
+        let steps = &steps;
+        
+        let step = |frontier: HashSet<u32>| setdo!{
+            x <- frontier;
+            y <- edges[&x].clone();
+            guard { steps.set( steps.get() + 1); true };
+            pure y
+        };
+        
+        // the paths through 2 and 3 both reach 4, which is expanded once on the next step
+        let two_steps = step( step( HashSet::pure( 1)));
+        assert_eq!( two_steps, HashSet::pure( 4));
+        assert_eq!( step( two_steps), HashSet::pure( 1));
+        assert_eq!( steps.get(), 5);
+        
+        let sums: BTreeSet<i32> = setdo!{
+            x <- (1..4).collect::<BTreeSet<_>>();
+            y <- 1..4;
+            guard x < y;
+            pure x + y
+        };
+        assert_eq!( sums.into_iter().collect::<Vec<_>>(), vec![3, 4, 5]);
+        
+        let mut heap = BinaryHeap::pure( 2);
+        heap.mplus( &mut vec![5, 1].into_iter().collect());
+        assert_eq!( heap.into_sorted_vec(), vec![1, 2, 5]);
+        
+        let settings = msum( vec![BTreeMap::pure( ("color", "red")), vec![("color", "blue"), ("size", "xl")].into_iter().collect()]);
+        assert_eq!( settings.into_iter().collect::<Vec<_>>(), vec![("color", "red"), ("size", "xl")]);
+        
+        let boxed: Box<[i32]> = msum( vec![Box::pure( 1), Box::mzero(), vec![2, 3].into_boxed_slice()]);
+        assert_eq!( mdo!{ x <- boxed; pure x * 2 }.collect::<Vec<_>>(), vec![2, 4, 6]);
+    }
+    
+    #[test]
+    fn test_tail_rec_m() {
+        use crate::monad::{tail_rec_m, Step};
+        
+        // a million iterations of a linear loop
+        let count = tail_rec_m( 0u32, |n| Some( if n < 1_000_000 {Step::Loop( n + 1)} else {Step::Done( n)}));
+        assert_eq!( count.collect::<Vec<_>>(), vec![1_000_000]);
+        
+        // branching: the binary words of length 2, in the order of `mdo`
+        let words = tail_rec_m( String::new(), |w| if w.len() == 2 {vec![Step::Done( w)]} 
+                                                   else {vec![Step::Loop( w.clone() + "0"), Step::Loop( w + "1")]});
+        assert_eq!( words.collect::<Vec<_>>(), vec!["00", "01", "10", "11"]);
+    }
 
+    #[test]
+    fn test_result_tail_rec_m() {
+        use crate::monad::{result_tail_rec_m, Step};
+        
+        let sum = result_tail_rec_m( (0u64, 0u32), |(acc, n)| Ok::<_, String>( if n < 1_000_000 {Step::Loop( (acc + n as u64, n + 1))} else {Step::Done( acc)}));
+        assert_eq!( sum, Ok( 499_999_500_000));
+        
+        // the first Err ends the loop
+        let parsed = result_tail_rec_m( (0, vec!["1", "2", "x", "y"]), |(acc, mut ws)| match ws.pop() {
+                        None => Ok( Step::Done( acc)),
+                        Some(w) => w.parse::<i32>().map( |n| Step::Loop( (acc + n, ws))).map_err( |_| w),
+                     });
+        assert_eq!( parsed, Err( "y"));
+    }
+}
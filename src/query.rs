@@ -0,0 +1,314 @@
+//! SQL like comprehensions over iterables, with the macro "qdo"
+//!
+//! As GHC's `TransformListComp`, the macro `qdo` accepts transformation stages (`then ...;`)
+//! between the bindings of a comprehension. A stage applies to the stream of all the variables in scope so far,
+//! so the macro keeps the list of bound variables and threads them as a tuple:
+//!
+//! * `then sort by key_expression;` stable sort of the rows by the key
+//! * `then take count;` and `then drop count;` to keep or skip the first rows
+//! * `then distinct;` to drop repeated rows, keeping the first occurrence
+//! * `join v <- iterable on left_key == right_key;` an equi-join, executed as a hash join:
+//!   the right side is indexed once by `right_key`, an expression on `v`,
+//!   and each row looks up its `left_key`, an expression on the variables in scope,
+//!   yielding the bindings of `v <- iterable; guard left_key == right_key;` in the same order.
+//! * `then group by key_expression;` to group the rows with equal key, ordered by key,
+//!   rebinding each variable in scope to the `Vec` of its values in the group;
+//!   with `then group by key_expression => key_var;` the group key is bound as well.
+//!
+//! The bound values must be `Clone`, as the key expressions are evaluated on copies of them,
+//! and `distinct` requires the rows to be `Eq + Hash`.
+//!
+//! The first source and the join sources are evaluated once, so they can't depend on the variables in scope; the next ones are evaluated per row inside `move` closures,
+//! so bind a reference to an outer collection (`let ys = &ys;`) before using it there.
+//!
+//! ```
+//! # #[macro_use] extern crate monadic;
+//!
+//! # fn main() {
+//! let sales = vec![("north", "tea", 3), ("south", "tea", 5), ("north", "coffee", 7), ("south", "tea", 1)];
+//!
+//! let totals = qdo!{
+//!     sale <- sales.iter();
+//!     let (region, product, amount) = *sale;
+//!     guard product == "tea";
+//!     then group by region => reg;
+//!     let total: i32 = amount.iter().sum();
+//!     then sort by -total;
+//!     pure (reg, total)
+//! }.collect::<Vec<_>>();
+//!
+//! assert_eq!( totals, vec![("south", 6), ("north", 3)]);
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// Rows (tuples of the variables in scope) split into a tuple of columns, for the stage `then group by`
+pub trait Columns: Sized {
+    type Cols;
+
+    fn columns(rows: Vec<Self>) -> Self::Cols;
+}
+
+macro_rules! columns_instances {
+  ($( ($($t:ident $c:ident),+) )*) => [$(
+    impl<$($t),+> Columns for ($($t,)+) {
+        type Cols = ($(Vec<$t>,)+);
+
+        #[allow(non_snake_case)]
+        fn columns(rows: Vec<Self>) -> Self::Cols {
+            $(let mut $c = Vec::with_capacity( rows.len());)+
+            for ($($t,)+) in rows {
+                $($c.push( $t);)+
+            }
+            ($($c,)+)
+        }
+    }
+  )*];
+}
+
+columns_instances!{
+    (A a)
+    (A a, B b)
+    (A a, B b, C c)
+    (A a, B b, C c, D d)
+    (A a, B b, C c, D d, E e)
+    (A a, B b, C c, D d, E e, F f)
+    (A a, B b, C c, D d, E e, F f, G g)
+    (A a, B b, C c, D d, E e, F f, G g, H h)
+}
+
+/// stable sort of the rows by their precomputed key
+pub fn sort_with<K: Ord, R>(keyed: impl Iterator<Item = (K, R)>) -> impl Iterator<Item = R> {
+    let mut rows = keyed.collect::<Vec<_>>();
+    rows.sort_by( |a, b| a.0.cmp( &b.0));
+    rows.into_iter().map( |(_, row)| row)
+}
+
+/// groups the rows with equal key, ordered by key, splitting each group in columns
+pub fn group_with<K: Ord, R: Columns>(keyed: impl Iterator<Item = (K, R)>) -> impl Iterator<Item = (K, R::Cols)> {
+    let mut rows = keyed.collect::<Vec<_>>();
+    rows.sort_by( |a, b| a.0.cmp( &b.0));
+    let mut groups: Vec<(K, Vec<R>)> = Vec::new();
+    for (key, row) in rows {
+        match groups.last_mut() {
+            Some((last, group)) if *last == key => group.push( row),
+            _ => groups.push( (key, vec![row])),
+        }
+    }
+    groups.into_iter().map( |(key, group)| (key, R::columns( group)))
+}
+
+/// indexes the keyed rows by key, keeping their order in each bucket, for the `join` clause
+pub fn hash_index<K: Eq + Hash, V>(keyed: impl Iterator<Item = (K, V)>) -> HashMap<K, Vec<V>> {
+    let mut index: HashMap<K, Vec<V>> = HashMap::new();
+    for (key, v) in keyed {
+        index.entry( key).or_default().push( v);
+    }
+    index
+}
+
+/// drops the repeated rows, keeping the first occurrence
+pub fn distinct<R: Clone + Eq + Hash>(rows: impl Iterator<Item = R>) -> impl Iterator<Item = R> {
+    let mut seen = HashSet::new();
+    rows.filter( move |row| seen.insert( row.clone()))
+}
+
+/// macro for SQL like comprehensions over iterables, with `then` stages over the variables in scope
+///
+/// You can use:
+/// * `pure return_expresion`    to return an expression value per row
+/// * `monadic_expression`       to end with an iterable per row
+/// * `v <- iterable_expression` to bind each item
+/// * `let v = expression`       to bind an expression value
+/// * `let (v1, v2) = expression` to destructure a tuple into variables
+/// * `guard boolean_expression` to filter rows
+/// * `join v <- iterable_expression on left_key == right_key` to hash join an iterable on equal keys
+/// * `then sort by key_expression` to sort the rows by key
+/// * `then take count`, `then drop count` to keep or skip the first rows
+/// * `then distinct`            to drop repeated rows
+/// * `then group by key_expression` to group the rows by key, rebinding the variables in scope to vectors
+/// * `then group by key_expression => key_var` to group by key, binding the key as well
+///
+#[macro_export]
+macro_rules! qdo {
+  (@rows [$($vs:ident)*] ($rows:expr) then sort by $key:expr ; $($rest:tt)*) => [
+        qdo!(@rows [$($vs)*] ($crate::query::sort_with( ($rows).map( qdo!(@keyed [$($vs)*] $key)))) $($rest)*)
+  ];
+  (@rows [$($vs:ident)*] ($rows:expr) then take $n:expr ; $($rest:tt)*) => [
+        qdo!(@rows [$($vs)*] (($rows).take( $n)) $($rest)*)
+  ];
+  (@rows [$($vs:ident)*] ($rows:expr) then drop $n:expr ; $($rest:tt)*) => [
+        qdo!(@rows [$($vs)*] (($rows).skip( $n)) $($rest)*)
+  ];
+  (@rows [$($vs:ident)*] ($rows:expr) then distinct ; $($rest:tt)*) => [
+        qdo!(@rows [$($vs)*] ($crate::query::distinct( $rows)) $($rest)*)
+  ];
+  (@rows [$($vs:ident)*] ($rows:expr) then group by $key:expr => $k:ident ; $($rest:tt)*) => [
+        qdo!(@rows [$($vs)* $k] ($crate::query::group_with( ($rows).map( qdo!(@keyed [$($vs)*] $key)))
+                                    .map( |($k, ($($vs,)*))| ($($vs,)* $k,))) $($rest)*)
+  ];
+  (@rows [$($vs:ident)*] ($rows:expr) then group by $key:expr ; $($rest:tt)*) => [
+        qdo!(@rows [$($vs)*] ($crate::query::group_with( ($rows).map( qdo!(@keyed [$($vs)*] $key)))
+                                .map( |(_, cols)| cols)) $($rest)*)
+  ];
+  (@rows [$($vs:ident)*] ($rows:expr) join $v:ident <- $($rest:tt)*) => [
+        qdo!(@join [$($vs)*] ($rows) $v [] $($rest)*)
+  ];
+  (@rows [$($vs:ident)*] ($rows:expr) $($step:tt)+) => [
+        $crate::row_step!(qdo $crate::monad::Bind::bind; [$($vs)*] ($rows) $($step)+)
+  ];
+
+  // join: the source tokens up to `on`, then the left key tokens up to `==`
+  (@join [$($vs:ident)*] ($rows:expr) $v:ident [$($src:tt)+] on $($rest:tt)*) => [
+        qdo!(@join_on [$($vs)*] ($rows) $v ($($src)+) [] $($rest)*)
+  ];
+  (@join [$($vs:ident)*] ($rows:expr) $v:ident [$($src:tt)*] $t:tt $($rest:tt)*) => [
+        qdo!(@join [$($vs)*] ($rows) $v [$($src)* $t] $($rest)*)
+  ];
+  (@join_on [$($vs:ident)*] ($rows:expr) $v:ident ($($src:tt)+) [$($lk:tt)+] == $rk:expr ; $($rest:tt)*) => [
+        qdo!(@rows [$($vs)* $v] ({
+                let index = $crate::query::hash_index( ::std::iter::IntoIterator::into_iter( $($src)+).map( |$v| {
+                                  let key = {
+                                      #[allow(clippy::redundant_clone)]
+                                      let $v = ::std::clone::Clone::clone( &$v);
+                                      $rk
+                                  };
+                                  (key, $v)
+                              }));
+                $crate::monad::Bind::bind( $rows, move |row| {
+                    $crate::row_step!(@unpack [$($vs)*] row);
+                    let matches = index.get( &($($lk)+)).cloned().unwrap_or_default();
+                    matches.into_iter().map( move |$v| ($($vs.clone(),)* $v,))
+                })
+             }) $($rest)*)
+  ];
+  (@join_on [$($vs:ident)*] ($rows:expr) $v:ident ($($src:tt)+) [$($lk:tt)*] $t:tt $($rest:tt)*) => [
+        qdo!(@join_on [$($vs)*] ($rows) $v ($($src)+) [$($lk)* $t] $($rest)*)
+  ];
+
+  // pairs each row with its key, evaluated on copies of the variables
+  (@keyed [$($vs:ident)*] $key:expr) => [
+        |row| {
+            let key = {
+                #[allow(unused_variables, clippy::redundant_clone)]
+                let ($($vs,)*) = ::std::clone::Clone::clone( &row);
+                $key
+            };
+            (key, row)
+        }
+  ];
+
+  // the first source is evaluated once, outside the closures
+  ($v:ident <- $monad:expr ; $($rest:tt)*) => [
+        qdo!(@rows [$v] (::std::iter::IntoIterator::into_iter( $monad).map( |$v| ($v,))) $($rest)*)
+  ];
+  ($($tokens:tt)+) => [qdo!(@rows [] (::std::iter::once(())) $($tokens)+)];
+}
+
+#[cfg(test)]
+mod tests {
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct Employee { name: &'static str, dept: &'static str, salary: u32 }
+
+    fn staff() -> Vec<Employee> {
+        vec![Employee{ name: "ann", dept: "dev", salary: 50},
+             Employee{ name: "bob", dept: "ops", salary: 40},
+             Employee{ name: "cid", dept: "dev", salary: 70},
+             Employee{ name: "dan", dept: "ops", salary: 45},
+             Employee{ name: "eve", dept: "hr", salary: 60}]
+    }
+
+    #[test]
+    fn test_sort_take_drop_distinct() {
+        let emps = staff();
+        let top = qdo!{
+            e <- emps.iter();
+            then sort by std::cmp::Reverse(e.salary);
+            then drop 1;
+            then take 2;
+            pure e.name
+        }.collect::<Vec<_>>();
+        assert_eq!( top, vec!["eve", "ann"]);
+
+        let depts = qdo!{
+            e <- emps.iter();
+            let d = e.dept;
+            then distinct;
+            pure d
+        }.collect::<Vec<_>>();
+        assert_eq!( depts, vec!["dev", "ops", "dev", "ops", "hr"]);
+
+        let depts = qdo!{
+            e <- emps.iter();
+            let d = e.dept;
+            guard d != "hr";
+            x <- vec![d];
+            then group by x;
+            then distinct;
+            pure d
+        }.collect::<Vec<_>>();
+        assert_eq!( depts, vec![vec!["dev", "dev"], vec!["ops", "ops"]]);
+    }
+
+    #[test]
+    fn test_group_by() {
+        let emps = staff();
+        let payroll = qdo!{
+            e <- emps.iter();
+            let (name, salary) = (e.name, e.salary);
+            then group by e.dept => dept;
+            let total: u32 = salary.iter().sum();
+            guard name.len() > 1;
+            pure (dept, name, total)
+        }.collect::<Vec<_>>();
+        assert_eq!( payroll, vec![("dev", vec!["ann", "cid"], 120), ("ops", vec!["bob", "dan"], 85)]);
+
+        let counts = qdo!{
+            x <- 1..=3;
+            y <- 1..=3;
+            guard x != y;
+            then group by x + y;
+            pure (x, y.len())
+        }.collect::<Vec<_>>();
+        assert_eq!( counts, vec![(vec![1, 2], 2), (vec![1, 3], 2), (vec![2, 3], 2)]);
+    }
+
+    #[test]
+    fn test_hash_join() {
+        let emps = staff();
+        let depts = vec![("dev", "Development"), ("ops", "Operations"), ("dev", "R&D")];
+
+        let joined = qdo!{
+            e <- emps.iter();
+            join d <- depts.iter() on e.dept == d.0;
+            guard e.salary < 60;
+            pure (e.name, d.1)
+        }.collect::<Vec<_>>();
+
+        let depts_ref = &depts;
+        let nested = qdo!{
+            e <- emps.iter();
+            d <- depts_ref.iter();
+            guard e.dept == d.0;
+            guard e.salary < 60;
+            pure (e.name, d.1)
+        }.collect::<Vec<_>>();
+
+        assert_eq!( joined, vec![("ann", "Development"), ("ann", "R&D"), ("bob", "Operations"), ("dan", "Operations")]);
+        assert_eq!( joined, nested);
+
+        // tens of thousands of rows
+        let orders = (0..50_000).map( |i| (i, i % 20_000)).collect::<Vec<(u32, u32)>>();
+        let customers = (0..20_000).map( |c| (c, c % 7)).collect::<Vec<(u32, u32)>>();
+        let count = qdo!{
+            o <- orders.iter();
+            join c <- customers.iter() on o.1 == c.0;
+            guard c.1 == 0;
+            pure o.0
+        }.count();
+        assert_eq!( count, orders.iter().filter( |o| o.1 % 7 == 0).count());
+    }
+}
@@ -1,5 +1,10 @@
 // mod reader
 
+use crate::monad::Step;
+use crate::hkt::{Kind, Kinded, Functor, Applicative, MonadK};
+use std::marker::PhantomData;
+use std::rc::Rc;
+
 pub struct Reader<'a, E, A> { 
   pub run_reader: Box< dyn 'a + Fn(E) -> A>, 
 }
@@ -37,12 +42,58 @@ impl<'a, E: 'a + Clone, A: 'a + Clone> Reader<'a, E, A> {
 
 }
 
+impl<'a, E: 'a, A: 'a> Reader<'a, E, A> {
+
+  /// runs the reader in the environment (Haskell's `runReader`), as `initial_env`
+  pub fn run_reader(self, e: E) -> A {
+       (* self.run_reader) (e)
+  }
+
+  /// runs the reader in an environment computed from another type (Haskell's `withReader`)
+  pub fn with_reader<E2, F>(self, f: F) -> Reader<'a, E2, A>
+     where
+       F: 'a + Fn(E2) -> E,
+  {
+    Reader { run_reader: 
+           Box::new(move |e: E2| { (*self.run_reader) (f(e)) })
+        }
+  }
+
+  /// maps the result (Haskell's `mapReader`)
+  pub fn map_reader<B, F>(self, f: F) -> Reader<'a, E, B>
+     where
+       F: 'a + Fn(A) -> B,
+  {
+    Reader { run_reader: 
+           Box::new(move |e: E| { f( (*self.run_reader) (e)) })
+        }
+  }
+}
+
 
 pub fn ask<'a, E: Clone>() -> Reader<'a, E, E> {
 
   Reader { run_reader: Box::new(|e: E| e.clone())}
 }
 
+/// a projection of the environment, without cloning it
+pub fn asks<'a, E, A, F>(f: F) -> Reader<'a, E, A>
+     where
+       F: 'a + Fn(&E) -> A,
+  {
+
+  Reader { run_reader: Box::new(move |e: E| f( &e))}
+}
+
+/// a Reader of an `(env -> a)` function
+pub fn reader<'a, E, A, F>(f: F) -> Reader<'a, E, A>
+     where
+       F: 'a + Fn(E) -> A,
+  {
+
+  Reader { run_reader: Box::new( f)}
+}
+
 pub fn local<'a, E, A, F>(f: F, rdr: Reader<'a, E, A>) -> Reader<'a, E, A>
      where
        F: 'a + Fn(E) -> E,
@@ -55,6 +106,202 @@ pub fn local<'a, E, A, F>(f: F, rdr: Reader<'a, E, A>) -> Reader<'a, E, A>
         }
   }
 
+/// stack safe loop, running `f` on the argument in the same environment until it returns `Step::Done`
+pub fn tail_rec_m<'a, E, A, B, F>(a: A, f: F) -> Reader<'a, E, B>
+     where
+       E: 'a + Clone,
+       A: 'a + Clone,
+       F: 'a + Fn(A) -> Reader<'a, E, Step<A, B>>,
+  {
+    Reader { run_reader: 
+           Box::new(move |e: E| {
+              let mut a = a.clone();
+              loop {
+                 match (* f( a).run_reader) (e.clone()) {
+                    Step::Loop(a1) => a = a1,
+                    Step::Done(b) => return b,
+                 }
+              }
+           })
+        }
+  }
+
+//--------------------------------------------
+
+/// the boxed `(&env -> r)` function of a shared environment
+pub type SharedRun<'a, E, R> = Box< dyn 'a + Fn(&Rc<E>) -> R>;
+
+/// a Reader of a shared environment, passed by reference through every bind, so it does not need `Clone`
+///
+/// `ask()` returns an `Rc` handle of the environment, and `asks( f)` a projection of it, used by the macro `shared_rdrdo`.
+pub struct SharedReader<'a, E, A> { 
+  pub run_shared_reader: SharedRun<'a, E, A>, 
+}
+
+impl<'a, E: 'a, A: 'a> SharedReader<'a, E, A> {
+
+  pub fn new<F>(f: F) -> Self
+     where F: 'a + Fn(&Rc<E>) -> A
+  {
+    SharedReader { run_shared_reader: Box::new( f)}
+  }
+
+  pub fn pure(x: A) -> Self
+     where A: Clone
+  {
+    SharedReader::new( move |_| x.clone())
+  }
+
+  /// a projection of the environment
+  pub fn asks<F>(f: F) -> Self
+     where F: 'a + Fn(&E) -> A
+  {
+    SharedReader::new( move |e: &Rc<E>| f( e))
+  }
+
+  pub fn bind<B: 'a, F>(self, f: F) -> SharedReader<'a, E, B>
+     where F: 'a + Fn(A) -> SharedReader<'a, E, B>
+  {
+    SharedReader::new( move |e: &Rc<E>| (* f( (* self.run_shared_reader)( e)).run_shared_reader)( e))
+  }
+
+  /// runs in an environment computed from the current one
+  pub fn local<F>(self, f: F) -> Self
+     where F: 'a + Fn(&E) -> E
+  {
+    SharedReader::new( move |e: &Rc<E>| (* self.run_shared_reader)( &Rc::new( f( e))))
+  }
+
+  pub fn initial_env(self, e: E) -> A {
+       (* self.run_shared_reader) (&Rc::new( e))
+  }
+
+  /// runs in a shared environment, that can be reused
+  pub fn run_shared(&self, e: &Rc<E>) -> A {
+       (* self.run_shared_reader) (e)
+  }
+}
+
+impl<'a, E: 'a> SharedReader<'a, E, Rc<E>> {
+
+  /// a handle of the environment
+  pub fn ask() -> Self {
+    SharedReader::new( |e: &Rc<E>| e.clone())
+  }
+}
+
+//--------------------------------------------
+
+/// a Reader of a statically dispatched `(env -> a)` function, without allocations nor virtual calls,
+/// used by the macro `static_rdrdo`
+///
+/// Its binds compose the closure types at compile time, so every branch of a block must have the same type,
+/// and recursive blocks need the boxed `Reader`, that `boxed()` converts to.
+#[derive(Clone, Copy)]
+pub struct ReaderFn<F>(F);
+
+impl ReaderFn<()> {
+
+  pub fn pure<E, A: Clone>(x: A) -> ReaderFn<impl Fn(E) -> A> {
+    ReaderFn( move |_| x.clone())
+  }
+
+  pub fn ask<E: Clone>() -> ReaderFn<impl Fn(E) -> E> {
+    ReaderFn( |e: E| e)
+  }
+
+  pub fn asks<E, A, G: Fn(&E) -> A>(f: G) -> ReaderFn<impl Fn(E) -> A> {
+    ReaderFn( move |e: E| f( &e))
+  }
+}
+
+impl<F> ReaderFn<F> {
+
+  pub fn new<E, A>(f: F) -> Self
+    where F: Fn(E) -> A
+  {
+    ReaderFn( f)
+  }
+
+  pub fn bind<E: Clone, A, B, G, N>(self, f: G) -> ReaderFn<impl Fn(E) -> B>
+    where
+      F: Fn(E) -> A,
+      G: Fn(A) -> ReaderFn<N>,
+      N: Fn(E) -> B,
+  {
+    ReaderFn( move |e: E| (f( (self.0) (e.clone())).0) (e))
+  }
+
+  pub fn local<E, A, G>(self, f: G) -> ReaderFn<impl Fn(E) -> A>
+    where
+      F: Fn(E) -> A,
+      G: Fn(E) -> E,
+  {
+    ReaderFn( move |e: E| (self.0) (f( e)))
+  }
+
+  pub fn initial_env<E, A>(&self, e: E) -> A
+    where F: Fn(E) -> A
+  {
+    (self.0) (e)
+  }
+
+  /// the type erased `Reader`
+  pub fn boxed<'a, E: 'a, A: 'a>(self) -> Reader<'a, E, A>
+    where F: 'a + Fn(E) -> A
+  {
+    Reader { run_reader: Box::new( self.0)}
+  }
+}
+
+//--------------------------------------------
+
+/// type constructor marker of `Reader<'a, E, _>` for the traits in module hkt
+pub struct ReaderK<E>(PhantomData<E>);
+
+impl<'a, E: 'a> Kind<'a> for ReaderK<E> {
+    type Of<A: 'a> = Reader<'a, E, A>;
+}
+
+impl<'a, E: 'a, A: 'a> Kinded<'a> for Reader<'a, E, A> {
+    type K = ReaderK<E>;
+    type Elem = A;
+
+    fn into_kind(self) -> Reader<'a, E, A> { self }
+}
+
+impl<'a, E: 'a> Functor<'a> for ReaderK<E> {
+
+    fn fmap<A: 'a, B: 'a, F>(fa: Reader<'a, E, A>, f: F) -> Reader<'a, E, B>
+       where F: 'a + Fn(A) -> B
+    {
+       Reader { run_reader: Box::new( move |e: E| f( (* fa.run_reader)( e))) }
+    }
+}
+
+impl<'a, E: 'a + Clone> Applicative<'a> for ReaderK<E> {
+
+    fn of<A: 'a + Clone>(x: A) -> Reader<'a, E, A> {
+       Reader::pure( x)
+    }
+
+    fn lift_a2<A: 'a + Clone, B: 'a + Clone, C: 'a, F>(fa: Reader<'a, E, A>, fb: Reader<'a, E, B>, f: F) -> Reader<'a, E, C>
+       where F: 'a + Fn(A, B) -> C
+    {
+       Reader { run_reader: 
+           Box::new( move |e: E| f( (* fa.run_reader)( e.clone()), (* fb.run_reader)( e)))
+       }
+    }
+}
+
+impl<'a, E: 'a + Clone> MonadK<'a> for ReaderK<E> {
+
+    fn flat_map<A: 'a + Clone, B: 'a, F>(fa: Reader<'a, E, A>, f: F) -> Reader<'a, E, B>
+       where F: 'a + Fn(A) -> Reader<'a, E, B>
+    {
+       fa.bind( f)
+    }
+}
   
 /// macro for a `Reader<'a, E, A>` monad with a boxed `(env -> a)` function  
 #[macro_export]
@@ -67,3 +314,130 @@ macro_rules! rdrdo {
   ($v:ident <- $monad:expr ; $($rest:tt)* ) => [Reader::bind(($monad), move |$v| { rdrdo!($($rest)*)} )];
   ($monad:expr                            ) => [$monad];
 }
+
+/// macro for a statically dispatched `ReaderFn` with the syntax of `rdrdo`, where `ask()` is `ReaderFn::ask()`
+///
+/// The bare forms `ask()` and `ask::<E>()` are read as the `ReaderFn` ones, so an `rdrdo` block
+/// without recursion ports unchanged.
+#[macro_export]
+macro_rules! static_rdrdo {
+  (pure $e:expr                           ) => [$crate::reader::ReaderFn::pure($e)];
+  (ask ()                                 ) => [$crate::reader::ReaderFn::ask()];
+  (let $p:pat = $e:expr ; $($rest:tt)*) => [{ let $p = $e; static_rdrdo!($($rest)*)}];
+  ($v:tt <- ask () ; $($rest:tt)* ) => [$crate::reader::ReaderFn::bind( $crate::reader::ReaderFn::ask(), move |$v| { static_rdrdo!($($rest)*)} )];
+  ($v:tt <- ask :: < $t:ty > () ; $($rest:tt)* ) => [$crate::reader::ReaderFn::bind( $crate::reader::ReaderFn::ask::<$t>(), move |$v| { static_rdrdo!($($rest)*)} )];
+  (_ <- $monad:expr ; $($rest:tt)* ) => [$crate::reader::ReaderFn::bind(($monad), move |_| { static_rdrdo!($($rest)*)} )];
+  ($v:ident <- pure $e:expr ; $($rest:tt)* ) => [$crate::reader::ReaderFn::bind( $crate::reader::ReaderFn::pure($e), move |$v| { static_rdrdo!($($rest)*)} )];
+  ($v:ident <- $monad:expr ; $($rest:tt)* ) => [$crate::reader::ReaderFn::bind(($monad), move |$v| { static_rdrdo!($($rest)*)} )];
+  ($monad:expr                            ) => [$monad];
+}
+
+/// macro for a `SharedReader<'a, E, A>` monad with the syntax of `rdrdo`, where `ask()` is `SharedReader::ask()`
+#[macro_export]
+macro_rules! shared_rdrdo {
+  (pure $e:expr                           ) => [SharedReader::pure($e)];
+  (let $v:ident = $e:expr ; $($rest:tt)*) => [SharedReader::pure($e).bind( move |$v| { shared_rdrdo!($($rest)*)} )];
+  (_ <- $monad:expr ; $($rest:tt)* ) => [SharedReader::bind(($monad), move |_| { shared_rdrdo!($($rest)*)} )];
+  ($v:ident <- pure $e:expr ; $($rest:tt)* ) => [SharedReader::bind( SharedReader::pure($e), move |$v| { shared_rdrdo!($($rest)*)} )];
+  ($v:ident <- $monad:expr ; $($rest:tt)* ) => [SharedReader::bind(($monad), move |$v| { shared_rdrdo!($($rest)*)} )];
+  ($monad:expr                            ) => [$monad];
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::{Reader, ReaderFn, ask, tail_rec_m};
+    use crate::monad::Step;
+    use crate::control::for_m;
+
+    #[derive(Clone)]
+    struct Config { port: u16, verbose: bool }
+
+    #[test]
+    fn test_static_reader() {
+        let block = static_rdrdo!{
+            cfg <- ReaderFn::ask::<Config>();
+            let port = cfg.port;
+            verbose <- ReaderFn::new( |c: Config| c.verbose);
+            pure if verbose {format!( "port {}", port)} else {String::new()}
+        };
+        assert_eq!( block.initial_env( Config { port: 80, verbose: true}), "port 80");
+        
+        // the inner block of examples/reader1.rs, unchanged
+        let ported = static_rdrdo!{ 
+        
+                x <- pure 9;
+                y <- ask();
+                pure (x, y)
+              };
+        assert_eq!( ported.initial_env( "env"), (9, "env"));
+        
+        let port = static_rdrdo!{
+            cfg <- ask::<Config>();
+            pure cfg.port
+        };
+        assert_eq!( port.initial_env( Config { port: 80, verbose: true}), 80);
+        
+        let quiet = block.local( |c: Config| Config { verbose: false, ..c});
+        assert_eq!( quiet.initial_env( Config { port: 80, verbose: true}), "");
+        
+        let boxed = quiet.boxed().bind( |s| ask().bind( move |c: Config| Reader::pure( format!( "{}{}", s, c.port))));
+        assert_eq!( boxed.initial_env( Config { port: 8080, verbose: true}), "8080");
+    }
+
+    #[test]
+    fn test_reader_api() {
+        use crate::reader::{asks, reader};
+        
+        let url = rdrdo!{
+            port <- asks( |c: &Config| c.port);
+            verbose <- reader( |c: Config| c.verbose);
+            pure format!( "localhost:{}{}", port, if verbose {" -v"} else {""})
+        };
+        let from_args = url.with_reader( |args: Vec<&str>| Config { port: args[0].parse().unwrap(), verbose: args.len() > 1});
+        let len = from_args.map_reader( |s| s.len());
+        assert_eq!( len.run_reader( vec!["8080", "-v"]), 17);
+        
+        assert_eq!( ReaderFn::asks( |c: &Config| c.port).initial_env( Config { port: 1, verbose: false}), 1);
+    }
+
+    #[test]
+    fn test_shared_reader() {
+        use crate::reader::SharedReader;
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        
+        // not Clone
+        struct Pool { conns: RefCell<Vec<u32>>, name: String }
+        
+        fn checkout<'a>() -> SharedReader<'a, Pool, Option<u32>> {
+            SharedReader::asks( |p: &Pool| p.conns.borrow_mut().pop())
+        }
+        
+        let block = shared_rdrdo!{
+            a <- checkout();
+            b <- checkout();
+            pool <- SharedReader::ask();
+            pure (a, b, pool.conns.borrow().len(), Rc::strong_count( &pool))
+        };
+        let pool = Rc::new( Pool { conns: RefCell::new( vec![1, 2, 3]), name: "db".into()});
+        assert_eq!( block.run_shared( &pool), (Some(3), Some(2), 1, 2));
+        assert_eq!( block.run_shared( &pool), (Some(1), None, 0, 2));
+        
+        let other = SharedReader::asks( |p: &Pool| p.name.clone()).local( |p: &Pool| Pool { conns: RefCell::new( vec![]), name: p.name.to_uppercase()});
+        assert_eq!( other.run_shared( &pool), "DB");
+    }
+
+    #[test]
+    fn test_tail_rec_m() {
+        const N: u32 = 1_000_000;
+        
+        let rdr = tail_rec_m( 0, |n| ask().bind( move |step: u32| Reader::pure( if n < N {Step::Loop( n + step)} else {Step::Done( n)})));
+        assert_eq!( rdr.initial_env( 2), N);
+    }
+
+    #[test]
+    fn test_traverse() {
+        let rdr = for_m( 1..4, |x| Reader::<'_, i32, _>::pure( x).bind( |x| ask().bind( move |e: i32| Reader::pure( x * e))));
+        assert_eq!( rdr.initial_env( 10), vec![10, 20, 30]);
+    }
+}
@@ -1,7 +1,11 @@
 // reader_trans
 
-use crate::monad::{Monad};
+use crate::monad::{self, Monad, Step};
+use crate::hkt::{Kind, Kinded, Functor, Applicative, MonadK};
+use crate::reader::SharedRun;
 use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::rc::Rc;
 
 
 pub struct ReaderT<'a, E, M> {                 // M: Monad + FromIterator
@@ -76,6 +80,173 @@ pub fn lift<'a, E: 'a, M: 'a + Clone>(m: M) -> ReaderT<'a, E, M> {
      ReaderT { run_reader_t: Box::new( move |_| m.clone() )}
 }
 
+/// stack safe loop over the inner monad results, running `f` on each `Loop` argument until it returns `Step::Done`;
+/// the results are collected in depth first order, as the ones of `bind`
+pub fn tail_rec_m<'a, E, M, N, A, B, F>(a: A, f: F) -> ReaderT<'a, E, N>
+     where
+       E: 'a + Clone,
+       A: 'a + Clone,
+       F: 'a + Fn(A) -> ReaderT<'a, E, M>,
+       M: 'a + IntoIterator<Item = Step<A, B>>,
+       N: 'a + FromIterator<B>,
+  {
+    ReaderT { run_reader_t: 
+           Box::new(move |e: E| {
+              monad::tail_rec_m( a.clone(), |a| (* f( a).run_reader_t) (e.clone())).collect::<N>()
+           })
+        }
+  }
+
+//--------------------------------------------
+
+/// a ReaderT of a shared environment, passed by reference through every bind, so it does not need `Clone`
+///
+/// `ask()` returns an `Rc` handle of the environment, and `asks( f)` a projection of it, used by the macro `shared_rdrt_mdo`.
+pub struct SharedReaderT<'a, E, M> {
+  pub run_shared_reader_t: SharedRun<'a, E, M>, 
+}
+
+impl<'a, A, E, M> SharedReaderT<'a, E, M> 
+    where
+      E: 'a, 
+      A: 'a,
+      M: 'a + Monad<Item=A>, 
+{
+
+  pub fn new<F>(f: F) -> Self
+     where F: 'a + Fn(&Rc<E>) -> M
+  {
+    SharedReaderT { run_shared_reader_t: Box::new( f)}
+  }
+
+  /// a projection of the environment in the inner monad
+  pub fn asks<F>(f: F) -> Self
+     where F: 'a + Fn(&E) -> A
+  {
+    SharedReaderT::new( move |e: &Rc<E>| M::pure( f( e)))
+  }
+
+  pub fn bind<B, N, F>(self, f: F) -> SharedReaderT<'a, E, N>
+        where 
+          F: 'a + Fn(A) -> SharedReaderT<'a, E, N>,
+          B: 'a,
+          N: 'a + Monad<Item=B> + FromIterator<B>,
+     {
+       SharedReaderT::new( move |e: &Rc<E>| { 
+           let m = (* self.run_shared_reader_t)( e);
+           let g = |a| (* f(a).run_shared_reader_t)( e);
+           M::bind( m, g).collect::<N>()
+           })
+     }
+
+  /// lift a monad
+  pub fn lift(m: M) -> Self
+     where M: Clone
+  {
+    SharedReaderT::new( move |_| m.clone())
+  }
+
+  /// lift from iterator
+  pub fn lift_iter<I>( it: I) -> Self
+     where 
+       I: 'a + Iterator<Item=A> + Clone,
+       M: FromIterator<A>,
+  {
+    SharedReaderT::new( move |_| it.clone().collect::<M>())
+  }
+
+  /// runs in an environment computed from the current one
+  pub fn local<F>(self, f: F) -> Self
+     where F: 'a + Fn(&E) -> E
+  {
+    SharedReaderT::new( move |e: &Rc<E>| (* self.run_shared_reader_t)( &Rc::new( f( e))))
+  }
+
+  /// applying initial_env() to (e -> m a) returns the inner monad structure
+  pub fn initial_env(self, e: E) -> M {
+       (* self.run_shared_reader_t) (&Rc::new( e))
+  }
+
+  /// runs in a shared environment, that can be reused
+  pub fn run_shared(&self, e: &Rc<E>) -> M {
+       (* self.run_shared_reader_t) (e)
+  }
+}
+
+impl<'a, E: 'a, M: 'a + Monad<Item=Rc<E>>> SharedReaderT<'a, E, M> {
+
+  /// a handle of the environment in the inner monad
+  pub fn ask() -> Self {
+    SharedReaderT::new( |e: &Rc<E>| M::pure( e.clone()))
+  }
+}
+
+//--------------------------------------------
+
+/// type constructor marker of `ReaderT<'a, E, MK::Of<_>>` over the inner monad marker `MK`, for the traits in module hkt
+pub struct ReaderTK<E, MK>(PhantomData<(E, MK)>);
+
+impl<'a, E: 'a, MK: Kind<'a>> Kind<'a> for ReaderTK<E, MK> {
+    type Of<A: 'a> = ReaderT<'a, E, MK::Of<A>>;
+}
+
+impl<'a, E: 'a, M: 'a + Kinded<'a>> Kinded<'a> for ReaderT<'a, E, M> {
+    type K = ReaderTK<E, M::K>;
+    type Elem = M::Elem;
+
+    fn into_kind(self) -> ReaderT<'a, E, <M::K as Kind<'a>>::Of<M::Elem>> {
+       ReaderT { run_reader_t: Box::new( move |e: E| (* self.run_reader_t)( e).into_kind()) }
+    }
+}
+
+impl<'a, E: 'a, MK: 'a + Functor<'a>> Functor<'a> for ReaderTK<E, MK> {
+
+    fn fmap<A: 'a, B: 'a, F>(fa: ReaderT<'a, E, MK::Of<A>>, f: F) -> ReaderT<'a, E, MK::Of<B>>
+       where F: 'a + Fn(A) -> B
+    {
+       let f = Rc::new( f);
+       ReaderT { run_reader_t: Box::new( move |e: E| {
+           let f = f.clone();
+           MK::fmap( (* fa.run_reader_t)( e), move |a| f( a))
+           })
+       }
+    }
+}
+
+impl<'a, E: 'a + Clone, MK: 'a + Applicative<'a>> Applicative<'a> for ReaderTK<E, MK> {
+
+    fn of<A: 'a + Clone>(x: A) -> ReaderT<'a, E, MK::Of<A>> {
+       ReaderT { run_reader_t: Box::new( move |_| MK::of( x.clone())) }
+    }
+
+    fn lift_a2<A: 'a + Clone, B: 'a + Clone, C: 'a, F>(fa: ReaderT<'a, E, MK::Of<A>>, fb: ReaderT<'a, E, MK::Of<B>>, f: F) 
+          -> ReaderT<'a, E, MK::Of<C>>
+       where F: 'a + Fn(A, B) -> C
+    {
+       let f = Rc::new( f);
+       ReaderT { run_reader_t: Box::new( move |e: E| {
+           let f = f.clone();
+           MK::lift_a2( (* fa.run_reader_t)( e.clone()), (* fb.run_reader_t)( e), move |a, b| f( a, b))
+           })
+       }
+    }
+}
+
+impl<'a, E: 'a + Clone, MK: 'a + MonadK<'a>> MonadK<'a> for ReaderTK<E, MK> {
+
+    fn flat_map<A: 'a + Clone, B: 'a, F>(fa: ReaderT<'a, E, MK::Of<A>>, f: F) -> ReaderT<'a, E, MK::Of<B>>
+       where F: 'a + Fn(A) -> ReaderT<'a, E, MK::Of<B>>
+    {
+       let f = Rc::new( f);
+       ReaderT { run_reader_t: Box::new( move |e: E| {
+           let f = f.clone();
+           let e1 = e.clone();
+           MK::flat_map( (* fa.run_reader_t)( e), move |a| (* f( a).run_reader_t)( e1.clone()))
+           })
+       }
+    }
+}
+
 /// macro for a `ReaderT<'a, E, M>` monad transformer with a boxed `(env -> m a) where M: Monad + FromIterator`. 
 /// It uses the type alias Env in type annotations
 #[macro_export]
@@ -103,3 +274,68 @@ macro_rules! rdrt_mdo {
   
   ($monad:expr                            ) => [$monad];
 }
+
+/// macro for a `SharedReaderT<'a, E, M>` monad transformer with the syntax of `rdrt_mdo`.
+/// It uses the type alias Env in type annotations
+#[macro_export]
+macro_rules! shared_rdrt_mdo {
+  (lift $last_nested_monad:expr                ) => [SharedReaderT::lift($last_nested_monad)];
+  
+  (pure $last_expr:expr                ) => [SharedReaderT::lift(vec!($last_expr))];
+  
+  (guard $boolean:expr ; $($rest:tt)*) => [SharedReaderT::lift( if $boolean {vec![()]} else {vec![]}).bind( move |_| { shared_rdrt_mdo!($($rest)*)} )];
+  
+  (let $v:ident = $e:expr ; $($rest:tt)*) => [SharedReaderT::lift(vec![$e]).bind( move |$v| { shared_rdrt_mdo!($($rest)*)} )];
+  
+  (_ <- $monad:expr ; $($rest:tt)* ) => [SharedReaderT::bind(($monad), move |_| { shared_rdrt_mdo!($($rest)*)} )];
+  
+  ($v:ident <- ask() ; $($rest:tt)* ) => [SharedReaderT::<'_, Env, Vec<::std::rc::Rc<Env>>>::ask().bind( 
+                                                         move |$v| { shared_rdrt_mdo!($($rest)*)}) ];
+                                                         
+  ($v:ident <- lift_iter $iterator:expr ; $($rest:tt)* ) => [SharedReaderT::<'_, Env, Vec<_>>::lift_iter($iterator).bind( move |$v| { shared_rdrt_mdo!($($rest)*)} )];
+  
+  (& $v:ident <- lift $nested_monad:expr ; $($rest:tt)* ) => [SharedReaderT::lift($nested_monad).bind( move |& $v| { shared_rdrt_mdo!($($rest)*)} )];
+  
+  ($v:ident <- lift $nested_monad:expr ; $($rest:tt)* ) => [SharedReaderT::lift($nested_monad).bind( move |$v| { shared_rdrt_mdo!($($rest)*)} )];
+  
+  ($v:ident <- $monad:expr ; $($rest:tt)* ) => [SharedReaderT::bind(($monad), move |$v| { shared_rdrt_mdo!($($rest)*)} )];
+  
+  ($monad:expr                            ) => [$monad];
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader_trans::{ReaderT, SharedReaderT, tail_rec_m};
+    use crate::monad::Step;
+    use std::cell::Cell;
+
+    // not Clone
+    struct Env { limit: u32, lookups: Cell<u32> }
+
+    #[test]
+    fn test_shared_reader_t() {
+        let block: SharedReaderT<'_, Env, Vec<(u32, u32)>> = shared_rdrt_mdo!{
+            env <- ask();
+            let limit = env.limit;
+            x <- lift_iter 1..4;
+            y <- SharedReaderT::<'_, Env, Vec<_>>::asks( |e: &Env| { e.lookups.set( e.lookups.get() + 1); e.limit});
+            guard x < y;
+            pure (x, limit - y)
+        };
+        let res = block.initial_env( Env { limit: 3, lookups: Cell::new( 0)});
+        assert_eq!( res, vec![(1, 0), (2, 0)]);
+        
+        let local = SharedReaderT::<'_, Env, Vec<u32>>::asks( |e: &Env| e.limit).local( |e: &Env| Env { limit: e.limit * 2, lookups: Cell::new( 0)});
+        assert_eq!( local.initial_env( Env { limit: 5, lookups: Cell::new( 0)}), vec![10]);
+    }
+
+    #[test]
+    fn test_tail_rec_m() {
+        const N: u32 = 1_000_000;
+        
+        let rdt: ReaderT<'_, u32, Vec<u32>> = tail_rec_m( 0, |n| ReaderT { 
+                     run_reader_t: Box::new( move |e: u32| if n < N {vec![Step::Loop( n + e)]} else {vec![Step::Done( n)]})
+                  });
+        assert_eq!( rdt.initial_env( 1), vec![N]);
+    }
+}
@@ -0,0 +1,148 @@
+//! A typed service map environment for `Reader` dependency injection
+//!
+//! `Services` holds one value of each type, shared by `Rc` handles, so it is cheap to clone at every bind of `Reader`.
+//! A block asks for the services it needs by type with `ask_of::<T>()`,
+//! and `local_of` overrides one service for a sub-block, so tests can swap individual services.
+//!
+//! A missing service is a run-time error: `try_ask_of` returns it as `Err(MissingService)`,
+//! and `ask_of` panics with its message, which `Services::require` can check before running.
+//!
+//! ```
+//! # #[macro_use] extern crate monadic;
+//! use monadic::{reader::Reader, services::{Services, ask_of, local_of}};
+//!
+//! # fn main() {
+//! struct Clock { now: u64 }
+//! struct Greeting(&'static str);
+//!
+//! let greet: Reader<'_, Services, String> = rdrdo!{
+//!     clock <- ask_of::<Clock>();
+//!     greeting <- ask_of::<Greeting>();
+//!     pure format!( "{} at {}", greeting.0, clock.now)
+//! };
+//! let env = Services::new().with( Clock { now: 10}).with( Greeting( "hello"));
+//!
+//! assert_eq!( local_of( Clock { now: 0}, greet).initial_env( env), "hello at 0");
+//! # }
+//! ```
+
+use crate::reader::{Reader, local};
+use std::any::{Any, TypeId, type_name};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+/// a map of services keyed by their type
+#[derive(Clone, Default)]
+pub struct Services {
+  map: Rc<HashMap<TypeId, Rc<dyn Any>>>,
+}
+
+/// the error of a service not found in `Services`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingService {
+  pub type_name: &'static str,
+}
+
+impl fmt::Display for MissingService {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+     write!( f, "missing service `{}`", self.type_name)
+  }
+}
+
+impl Error for MissingService {}
+
+impl Services {
+
+  pub fn new() -> Self {
+     Services::default()
+  }
+
+  /// adds or replaces the service of type `T`
+  pub fn with<T: 'static>(self, service: T) -> Self {
+     self.with_rc( Rc::new( service))
+  }
+
+  /// adds or replaces the service of type `T` by its handle
+  pub fn with_rc<T: 'static>(mut self, service: Rc<T>) -> Self {
+     Rc::make_mut( &mut self.map).insert( TypeId::of::<T>(), service);
+     self
+  }
+
+  /// a handle of the service of type `T`
+  pub fn get<T: 'static>(&self) -> Result<Rc<T>, MissingService> {
+     self.map.get( &TypeId::of::<T>())
+             .and_then( |service| service.clone().downcast::<T>().ok())
+             .ok_or( MissingService { type_name: type_name::<T>()})
+  }
+
+  /// checks that the service of type `T` is present
+  pub fn require<T: 'static>(self) -> Result<Self, MissingService> {
+     self.get::<T>().map( |_| self)
+  }
+
+  pub fn contains<T: 'static>(&self) -> bool {
+     self.map.contains_key( &TypeId::of::<T>())
+  }
+}
+
+/// the service of type `T`, or the `MissingService` error
+pub fn try_ask_of<'a, T: 'static>() -> Reader<'a, Services, Result<Rc<T>, MissingService>> {
+   Reader { run_reader: Box::new( |env: Services| env.get::<T>())}
+}
+
+/// the service of type `T`
+///
+/// It panics with the `MissingService` message if there is none.
+pub fn ask_of<'a, T: 'static>() -> Reader<'a, Services, Rc<T>> {
+   Reader { run_reader: Box::new( |env: Services| env.get::<T>().unwrap_or_else( |err| panic!( "{}", err)))}
+}
+
+/// runs a Reader with the service of type `T` replaced
+pub fn local_of<'a, T: 'static, A: 'a + Clone>(service: T, rdr: Reader<'a, Services, A>) -> Reader<'a, Services, A> {
+   let service = Rc::new( service);
+   local( move |env: Services| env.with_rc( service.clone()), rdr)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rdrdo;
+    use crate::reader::Reader;
+    use crate::services::{Services, MissingService, ask_of, try_ask_of, local_of};
+    use std::cell::RefCell;
+
+    struct Logger { lines: RefCell<Vec<String>> }
+    struct Clock { now: u64 }
+
+    fn log<'a>(msg: &'a str) -> Reader<'a, Services, ()> {
+        rdrdo!{
+            logger <- ask_of::<Logger>();
+            clock <- ask_of::<Clock>();
+            pure logger.lines.borrow_mut().push( format!( "{}: {}", clock.now, msg))
+        }
+    }
+
+    #[test]
+    fn test_services() {
+        let block = rdrdo!{
+            _ <- log( "start");
+            _ <- local_of( Clock { now: 99}, log( "mocked"));
+            _ <- log( "end");
+            logger <- ask_of::<Logger>();
+            pure logger.lines.borrow().clone()
+        };
+        let env = Services::new().with( Logger { lines: RefCell::new( vec![])}).with( Clock { now: 1});
+        assert_eq!( block.initial_env( env.clone()), vec!["1: start", "99: mocked", "1: end"]);
+        
+        let missing = Services::new().with( Clock { now: 1});
+        assert!( !missing.contains::<Logger>());
+        assert_eq!( missing.clone().require::<Logger>().err().map( |err| err.to_string()),
+                    Some( format!( "missing service `{}`", std::any::type_name::<Logger>())));
+        assert_eq!( try_ask_of::<Clock>().initial_env( missing).map( |clock| clock.now), Ok(1));
+        assert!( try_ask_of::<Logger>().initial_env( Services::new()).is_err());
+        
+        let err: MissingService = Services::new().get::<Clock>().err().unwrap();
+        assert!( err.type_name.ends_with( "Clock"));
+    }
+}
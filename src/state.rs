@@ -1,42 +1,386 @@
 //! A State monad implementation
+//!
+//! A `State` is run by a trampoline: each step returns the rest of the computation to a loop in `run`
+//! instead of calling it, so arbitrarily deep chains of binds, left nested or recursive, run in constant stack.
+//!
+//! Dropping does not take constant stack in every case: a left nested chain of binds is dropped in a loop,
+//! but a State captured by a bind continuation (as in `m.bind( move |_| other.clone())`) is dropped
+//! with the closure, recursively, so dropping a chain of many thousands of such captures may overflow the stack.
+
+use crate::monad::Step;
+use crate::hkt::{Kind, Kinded, Functor, Applicative, MonadK};
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::mem;
+use std::rc::Rc;
 
 pub struct State<'a, S, A> { 
-  pub run_state: Box<dyn 'a + Fn(S) -> (A, S)>, 
+  run_state: Rc<dyn 'a + Node<'a, S, A>>, 
+}
+
+/// the result of a step of the trampoline, done or the next step
+enum Bounce<'a> {
+  Done,
+  More(Box<dyn 'a + FnOnce() -> Bounce<'a>>),
+}
+
+/// the continuation of a step, taking its result and state
+type Cont<'a, S, A> = Box<dyn 'a + FnOnce(A, S) -> Bounce<'a>>;
+
+/// the pending sub-computations of a State being dropped
+type Detached<'a> = Vec<Box<dyn 'a + Detach<'a>>>;
+
+trait Node<'a, S, A> {
+  /// a step, passing the result to the continuation in the returned bounce
+  fn run(&self, s: S, k: Cont<'a, S, A>) -> Bounce<'a>;
+  
+  /// moves out the sub-computations, to drop them in a loop
+  fn detach(&mut self, _out: &mut Detached<'a>) {}
+}
+
+trait Detach<'a> {
+  fn detach(&mut self, out: &mut Detached<'a>);
+}
+
+/// a plain `(s -> (a, s))` function
+struct FnNode<F>(F);
+
+impl<'a, S: 'a, A: 'a, F> Node<'a, S, A> for FnNode<F>
+  where F: Fn(S) -> (A, S)
+{
+  fn run(&self, s: S, k: Cont<'a, S, A>) -> Bounce<'a> {
+     let (a, s1) = (self.0) (s);
+     Bounce::More( Box::new( move || k( a, s1)))
+  }
+}
+
+/// `m` followed by `then` on its result
+struct ThenNode<'a, S, X, G> {
+  m: Option<State<'a, S, X>>,
+  then: Rc<G>,
 }
 
-impl<'a, S: 'a + Clone, A: 'a + Clone> State<'a, S, A> {
+impl<'a, S: 'a, X: 'a, A: 'a, G> Node<'a, S, A> for ThenNode<'a, S, X, G>
+  where G: 'a + Fn(X, S, Cont<'a, S, A>) -> Bounce<'a>
+{
+  fn run(&self, s: S, k: Cont<'a, S, A>) -> Bounce<'a> {
+     let m = self.m.clone().expect( "State: running a detached node");
+     let then = self.then.clone();
+     // the outer step returns before running `m`, so left nested binds don't grow the stack
+     Bounce::More( Box::new( move || (m.run_state).run( s, Box::new( move |x, s1| then( x, s1, k)))))
+  }
+  
+  fn detach(&mut self, out: &mut Detached<'a>) {
+     if let Some(m) = self.m.take() {
+        out.push( Box::new( m));
+     }
+  }
+}
+
+impl<'a, S: 'a, A: 'a> Detach<'a> for State<'a, S, A> {
+  fn detach(&mut self, out: &mut Detached<'a>) {
+     if let Some(node) = Rc::get_mut( &mut self.run_state) {
+        node.detach( out);
+     }
+  }
+}
+
+impl<'a, S, A> Clone for State<'a, S, A> {
+  fn clone(&self) -> Self {
+     State { run_state: self.run_state.clone()}
+  }
+}
+
+impl<'a, S, A> Drop for State<'a, S, A> {
+  fn drop(&mut self) {
+     // a left nested chain of binds is a chain of nodes, unlinked here instead of dropped recursively
+     if let Some(node) = Rc::get_mut( &mut self.run_state) {
+        let mut pending: Detached<'_> = Vec::new();
+        node.detach( &mut pending);
+        while let Some(mut st) = pending.pop() {
+           st.detach( &mut pending);
+        }
+     }
+  }
+}
+
+impl<'a, S: 'a, A: 'a> State<'a, S, A> {
+
+  /// a State of a `(s -> (a, s))` function
+  pub fn new<F>(f: F) -> Self
+    where F: 'a + Fn(S) -> (A, S)
+  {
+    State { run_state: Rc::new( FnNode( f))}
+  }
+
+  /// `m` followed by `then` on its result and state, passing it the continuation
+  fn then<X: 'a, G>(m: State<'a, S, X>, then: G) -> Self
+    where G: 'a + Fn(X, S, Cont<'a, S, A>) -> Bounce<'a>
+  {
+    State { run_state: Rc::new( ThenNode { m: Some(m), then: Rc::new( then)})}
+  }
 
-  pub fn pure(x: A) -> Self {
-    State { run_state: Box::new( move |s: S| (x.clone(), s))}  // (s -> (a,s))
+  pub fn pure(x: A) -> Self
+    where A: Clone
+  {
+    State::new( move |s: S| (x.clone(), s))  // (s -> (a,s))
   }
 
-  pub fn bind<B, F: 'a>(self, f: F) -> State<'a, S, B> 
+  pub fn bind<B: 'a, F: 'a>(self, f: F) -> State<'a, S, B> 
     where
       F: Fn(A) -> State<'a, S, B>
   {
-    State { run_state: Box::new( move |s: S| {
-                  let (v, s1) = (*self.run_state) (s); // let (v,s') = runState self s
-                  let g = f( v).run_state ;
-                  (* g) (s1)             // runState (f v) s'
+    // runState (f v) s'
+    State::then( self, move |v, s1, k| (f( v).run_state).run( s1, k))
+  }
+
+  /// the result, dropping the final state
+  pub fn eval_state(self, s: S) -> A {
+       self.initial_state( s).0
+  }
+
+  /// the final state, dropping the result
+  pub fn exec_state(self, s: S) -> S {
+       self.initial_state( s).1
+  }
+
+  /// runs with the initial state modified by `f`
+  pub fn with_state<F>(self, f: F) -> Self
+    where F: 'a + Fn(S) -> S
+  {
+    modify( f).bind( move |_| self.clone())
+  }
+
+  /// maps the result and the final state
+  pub fn map_state<B: 'a, F>(self, f: F) -> State<'a, S, B>
+    where F: 'a + Fn((A, S)) -> (B, S)
+  {
+    State::then( self, move |a, s1, k| {
+                  let (b, s2) = f( (a, s1));
+                  Bounce::More( Box::new( move || k( b, s2)))
                })
-          }     
   }
 
+  /// runs the State from the initial state, returning the result and the final state (Haskell's `runState`)
+  pub fn run(self, s: S) -> (A, S) {
+       let result = Rc::new( RefCell::new( None));
+       let slot = result.clone();
+       let mut bounce = (self.run_state).run( s, Box::new( move |a, s1| {
+                               *slot.borrow_mut() = Some((a, s1));
+                               Bounce::Done
+                            }));
+       while let Bounce::More(step) = bounce {
+          bounce = step();
+       }
+       let mut result = result.borrow_mut();
+       mem::take( &mut *result).expect( "State: the trampoline ended without result")
+  }
+
+  /// same as `run`
   pub fn initial_state(self, s: S) -> (A, S) {
-       (*self.run_state) (s)
+       self.run( s)
   }
   
 }
 
-pub fn get<'a, S: Clone>() -> State<'a, S, S> {
-   State { run_state: Box::new( |s: S| (s.clone(), s))} 
+pub fn get<'a, S: 'a + Clone>() -> State<'a, S, S> {
+   State::new( |s: S| (s.clone(), s))
 }
 
 pub fn put<'a, S: Clone + 'a>( s: S) -> State<'a, S, ()> {
-   State { run_state: Box::new( move |_| ( (), s.clone()) )} 
+   State::new( move |_| ( (), s.clone()))
+}
+
+/// a State of a `(s -> (a, s))` function
+pub fn state<'a, S: 'a, A: 'a, F>(f: F) -> State<'a, S, A>
+  where F: 'a + Fn(S) -> (A, S)
+{
+   State::new( f)
+}
+
+/// updates the state, without cloning it
+pub fn modify<'a, S: 'a, F>(f: F) -> State<'a, S, ()>
+  where F: 'a + Fn(S) -> S
+{
+   State::new( move |s: S| ((), f( s)))
+}
+
+/// a projection of the state, without cloning it
+pub fn gets<'a, S: 'a, A: 'a, F>(f: F) -> State<'a, S, A>
+  where F: 'a + Fn(&S) -> A
+{
+   State::new( move |s: S| (f( &s), s))
+}
+
+/// runs a State on a part of the state, taken out by `getter` and put back by `setter`
+///
+/// The getter may clone the part or move it out (as `mem::take( &mut s.field)`), so the rest of the state is not cloned.
+pub fn zoom<'a, S: 'a, T: 'a, A: 'a, G, H>(getter: G, setter: H, st: State<'a, T, A>) -> State<'a, S, A>
+  where
+    G: 'a + Fn(&mut S) -> T,
+    H: 'a + Fn(&mut S, T),
+{
+   let setter = Rc::new( setter);
+   let split = State::new( move |mut s: S| {
+                  let t = getter( &mut s);
+                  (t, s)
+               });
+   State::then( split, move |t, s, k| {
+                  let setter = setter.clone();
+                  (st.run_state).run( t, Box::new( move |a, t1| {
+                        let mut s = s;
+                        setter( &mut s, t1);
+                        Bounce::More( Box::new( move || k( a, s)))
+                     }))
+               })
+}
+
+/// stack safe loop, running `f` on the argument and threading the state until it returns `Step::Done`
+pub fn tail_rec_m<'a, S, A, B: 'a, F>(a: A, f: F) -> State<'a, S, B>
+  where
+    S: 'a,
+    A: 'a + Clone,
+    F: 'a + Fn(A) -> State<'a, S, Step<A, B>>,
+{
+   State::new( move |mut s: S| {
+                  let mut a = a.clone();
+                  loop {
+                     let (step, s1) = f( a).initial_state( s);
+                     s = s1;
+                     match step {
+                        Step::Loop(a1) => a = a1,
+                        Step::Done(b) => return (b, s),
+                     }
+                  }
+               })
 }
 
-/// macro for a `State<'a, S, A>` monad with a boxed `(s -> (a, s))` function
+//--------------------------------------------
+
+/// a State of a statically dispatched `(s -> (a, s))` function, without allocations nor virtual calls,
+/// used by the macro `static_stdo`
+///
+/// Its binds compose the closure types at compile time, so every branch of a block must have the same type,
+/// and recursive blocks need the boxed `State`, that `boxed()` converts to.
+#[derive(Clone, Copy)]
+pub struct StateFn<F>(F);
+
+impl StateFn<()> {
+
+  pub fn pure<S, A: Clone>(x: A) -> StateFn<impl Fn(S) -> (A, S)> {
+    StateFn( move |s: S| (x.clone(), s))
+  }
+
+  pub fn get<S: Clone>() -> StateFn<impl Fn(S) -> (S, S)> {
+    StateFn( |s: S| (s.clone(), s))
+  }
+
+  pub fn put<S: Clone>(s: S) -> StateFn<impl Fn(S) -> ((), S)> {
+    StateFn( move |_| ((), s.clone()))
+  }
+
+  pub fn modify<S, G: Fn(S) -> S>(f: G) -> StateFn<impl Fn(S) -> ((), S)> {
+    StateFn( move |s: S| ((), f( s)))
+  }
+
+  pub fn gets<S, A, G: Fn(&S) -> A>(f: G) -> StateFn<impl Fn(S) -> (A, S)> {
+    StateFn( move |s: S| (f( &s), s))
+  }
+}
+
+impl<F> StateFn<F> {
+
+  pub fn new<S, A>(f: F) -> Self
+    where F: Fn(S) -> (A, S)
+  {
+    StateFn( f)
+  }
+
+  pub fn bind<S, A, B, G, N>(self, f: G) -> StateFn<impl Fn(S) -> (B, S)>
+    where
+      F: Fn(S) -> (A, S),
+      G: Fn(A) -> StateFn<N>,
+      N: Fn(S) -> (B, S),
+  {
+    StateFn( move |s: S| {
+        let (v, s1) = (self.0) (s);
+        (f( v).0) (s1)
+    })
+  }
+
+  pub fn initial_state<S, A>(&self, s: S) -> (A, S)
+    where F: Fn(S) -> (A, S)
+  {
+    (self.0) (s)
+  }
+
+  /// the type erased `State`
+  pub fn boxed<'a, S: 'a, A: 'a>(self) -> State<'a, S, A>
+    where F: 'a + Fn(S) -> (A, S)
+  {
+    State::new( self.0)
+  }
+}
+
+//--------------------------------------------
+
+/// type constructor marker of `State<'a, S, _>` for the traits in module hkt
+pub struct StateK<S>(PhantomData<S>);
+
+impl<'a, S: 'a> Kind<'a> for StateK<S> {
+    type Of<A: 'a> = State<'a, S, A>;
+}
+
+impl<'a, S: 'a, A: 'a> Kinded<'a> for State<'a, S, A> {
+    type K = StateK<S>;
+    type Elem = A;
+
+    fn into_kind(self) -> State<'a, S, A> { self }
+}
+
+impl<'a, S: 'a> Functor<'a> for StateK<S> {
+
+    fn fmap<A: 'a, B: 'a, F>(fa: State<'a, S, A>, f: F) -> State<'a, S, B>
+       where F: 'a + Fn(A) -> B
+    {
+       State::then( fa, move |a, s1, k| {
+                  let b = f( a);
+                  Bounce::More( Box::new( move || k( b, s1)))
+               })
+    }
+}
+
+impl<'a, S: 'a + Clone> Applicative<'a> for StateK<S> {
+
+    fn of<A: 'a + Clone>(x: A) -> State<'a, S, A> {
+       State::pure( x)
+    }
+
+    /// runs `fa` before `fb`, threading the state
+    fn lift_a2<A: 'a + Clone, B: 'a + Clone, C: 'a, F>(fa: State<'a, S, A>, fb: State<'a, S, B>, f: F) -> State<'a, S, C>
+       where F: 'a + Fn(A, B) -> C
+    {
+       let f = Rc::new( f);
+       State::then( fa, move |a, s1, k| {
+                  let f = f.clone();
+                  (fb.run_state).run( s1, Box::new( move |b, s2| {
+                        let c = f( a, b);
+                        Bounce::More( Box::new( move || k( c, s2)))
+                     }))
+               })
+    }
+}
+
+impl<'a, S: 'a + Clone> MonadK<'a> for StateK<S> {
+
+    fn flat_map<A: 'a + Clone, B: 'a, F>(fa: State<'a, S, A>, f: F) -> State<'a, S, B>
+       where F: 'a + Fn(A) -> State<'a, S, B>
+    {
+       fa.bind( f)
+    }
+}
+
+/// macro for a `State<'a, S, A>` monad, a trampolined `(s -> (a, s))` function
 #[macro_export]
 macro_rules! stdo {
   (pure $e:expr                           ) => [State::pure($e)];
@@ -47,4 +391,204 @@ macro_rules! stdo {
   ($monad:expr                            ) => [$monad];
 }
 
+/// `zoom` on a field of a struct state, moving it out with `mem::take`, so its type must implement `Default`
+///
+/// `zoom_field!( AppState, counter, action)` runs `action` on the field `counter` of the state `AppState`,
+/// with the function `zoom` in scope (from module `state` or `state_trans`).
+#[macro_export]
+macro_rules! zoom_field {
+  ($s:ty, $field:ident, $action:expr) => [
+        zoom( |s: &mut $s| ::std::mem::take( &mut s.$field), |s: &mut $s, t| s.$field = t, $action)
+  ];
+}
 
+/// macro for a statically dispatched `StateFn` with the syntax of `stdo`, 
+/// where `get()` and `put( s)` are `StateFn::get()` and `StateFn::put( s)`
+///
+/// The bare forms `get()`, `get::<S>()` and `put( s)` are read as the `StateFn` ones, so a `stdo` block
+/// without recursion ports unchanged.
+#[macro_export]
+macro_rules! static_stdo {
+  (pure $e:expr                           ) => [$crate::state::StateFn::pure($e)];
+  (get ()                                 ) => [$crate::state::StateFn::get()];
+  (put ($e:expr)                          ) => [$crate::state::StateFn::put($e)];
+  (let $p:pat = $e:expr ; $($rest:tt)*) => [{ let $p = $e; static_stdo!($($rest)*)}];
+  ($v:tt <- get () ; $($rest:tt)* ) => [$crate::state::StateFn::bind( $crate::state::StateFn::get(), move |$v| { static_stdo!($($rest)*)} )];
+  ($v:tt <- get :: < $t:ty > () ; $($rest:tt)* ) => [$crate::state::StateFn::bind( $crate::state::StateFn::get::<$t>(), move |$v| { static_stdo!($($rest)*)} )];
+  ($v:tt <- put ($e:expr) ; $($rest:tt)* ) => [$crate::state::StateFn::bind( $crate::state::StateFn::put($e), move |$v| { static_stdo!($($rest)*)} )];
+  (_ <- $monad:expr ; $($rest:tt)* ) => [$crate::state::StateFn::bind(($monad), move |_| { static_stdo!($($rest)*)} )];
+  ($v:ident <- pure $e:expr ; $($rest:tt)* ) => [$crate::state::StateFn::bind( $crate::state::StateFn::pure($e), move |$v| { static_stdo!($($rest)*)} )];
+  ($v:ident <- $monad:expr ; $($rest:tt)* ) => [$crate::state::StateFn::bind(($monad), move |$v| { static_stdo!($($rest)*)} )];
+  ($monad:expr                            ) => [$monad];
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use crate::state::{State, get, put, tail_rec_m};
+    use crate::monad::Step;
+    use crate::control::{for_m_, traverse};
+    use crate::hkt::{Functor, lift_a2};
+    use crate::state::StateK;
+
+    fn count_down<'a>(n: u32) -> State<'a, u64, ()> {
+        stdo!{
+            s <- get();
+            _ <- put( s + 1);
+            if n == 0 {State::pure( ())} else {count_down( n - 1)}
+        }
+    }
+
+    #[test]
+    fn test_deep_binds() {
+        const N: u32 = 1_000_000;
+        
+        // a left nested chain of binds
+        let mut st: State<'_, u32, u32> = State::pure( 0);
+        for _ in 0..N {
+            st = st.bind( |x| get().bind( move |s: u32| put( s + 1).bind( move |_| State::pure( x + 1))));
+        }
+        assert_eq!( st.clone().initial_state( 0), (N, N));
+        
+        // a left nested chain of fmap
+        let mut fm = get::<u32>();
+        for _ in 0..N {
+            fm = StateK::fmap( fm, |x| x + 1);
+        }
+        assert_eq!( fm.initial_state( 1), (N + 1, 1));
+        
+        // a recursive block
+        assert_eq!( count_down( N).initial_state( 0), ((), N as u64 + 1));
+        
+        let pair = lift_a2( get::<u32>(), put( 5).bind( |_| State::pure( 7)), |a, b| (a, b));
+        assert_eq!( pair.initial_state( 3), ((3, 7), 5));
+    }
+
+    /// a right nested chain, each State captured by the continuation of the next one
+    fn captured_chain<'a>(n: u32) -> State<'a, u32, u32> {
+        let mut st: State<'_, u32, u32> = State::new( |s| (0, s + 1));
+        for _ in 0..n {
+            let prev = st;
+            st = State::pure( 0).bind( move |_| prev.clone());
+        }
+        st
+    }
+
+    #[test]
+    fn test_captured_states() {
+        // dropping captured states recurses (see the module doc), so the chain is only moderately deep
+        let st = captured_chain( 1_000);
+        assert_eq!( st.clone().run( 0), (0, 1));
+        drop( st);
+    }
+
+    #[test]
+    fn test_static_state() {
+        use crate::state::StateFn;
+        
+        let block = static_stdo!{
+            x <- pure 9;
+            y <- StateFn::get();
+            _ <- StateFn::put( y * 2);
+            let z = x + y;
+            pure (x, z)
+        };
+        assert_eq!( block.initial_state( 1), ((9, 10), 2));
+        assert_eq!( block.initial_state( 2), ((9, 11), 4));
+        
+        // the block of examples/state1.rs, unchanged
+        let ported = static_stdo!{ 
+       
+            x <- pure 9;
+            y <- get();
+            
+            _ <- put( 1);
+            z <- get(); 
+            
+            pure (x, y, z) 
+        };
+        assert_eq!( ported.initial_state( 4), ((9, 4, 1), 1));
+        
+        let typed = static_stdo!{
+            s <- get::<u8>();
+            put( s + 1)
+        };
+        assert_eq!( typed.initial_state( 1), ((), 2));
+        
+        let boxed: State<'_, i32, _> = block.boxed().bind( |(x, z)| get().bind( move |s| State::pure( x + z + s)));
+        assert_eq!( boxed.initial_state( 1), (21, 2));
+    }
+
+    #[test]
+    fn test_state_api() {
+        use crate::state::{StateFn, state, modify, gets};
+        
+        #[derive(Debug, PartialEq)]
+        struct Counter { hits: u32, name: String }
+        
+        // no clone of the state
+        let hit = stdo!{
+            _ <- modify( |c: Counter| Counter { hits: c.hits + 1, ..c});
+            gets( |c: &Counter| c.hits)
+        };
+        let twice = hit.clone().bind( move |_| hit.clone());
+        assert_eq!( twice.clone().eval_state( Counter { hits: 0, name: "a".into()}), 2);
+        assert_eq!( twice.clone().exec_state( Counter { hits: 5, name: "a".into()}), Counter { hits: 7, name: "a".into()});
+        
+        let reset = twice.with_state( |c: Counter| Counter { hits: 0, ..c});
+        let scaled = reset.map_state( |(n, c)| (n * 10, Counter { name: c.name + "!", ..c}));
+        assert_eq!( scaled.initial_state( Counter { hits: 5, name: "a".into()}), (20, Counter { hits: 2, name: "a!".into()}));
+        
+        let pop = state( |mut xs: Vec<i32>| (xs.pop(), xs));
+        assert_eq!( pop.initial_state( vec![1, 2]), (Some(2), vec![1]));
+        
+        let static_hit = StateFn::modify( |n: u32| n + 1).bind( |_| StateFn::gets( |n: &u32| n * 2));
+        assert_eq!( static_hit.initial_state( 1), (4, 2));
+    }
+
+    #[test]
+    fn test_zoom() {
+        use crate::state::{zoom, modify, gets};
+        
+        #[derive(Default, Debug, PartialEq)]
+        struct Counter { hits: u32 }
+        
+        #[derive(Debug, PartialEq)]
+        struct AppState { counter: Counter, log: Vec<String> }
+        
+        fn hit<'a>() -> State<'a, Counter, u32> {
+            stdo!{
+                _ <- modify( |c: Counter| Counter { hits: c.hits + 1});
+                gets( |c: &Counter| c.hits)
+            }
+        }
+        
+        let app: State<'_, AppState, u32> = stdo!{
+            _ <- zoom_field!( AppState, counter, hit());
+            _ <- zoom( |s: &mut AppState| s.log.len(), |s: &mut AppState, n| s.log.push( n.to_string()), modify( |n: usize| n * 10));
+            zoom_field!( AppState, counter, hit())
+        };
+        let init = AppState { counter: Counter { hits: 0}, log: vec!["start".into()]};
+        assert_eq!( app.initial_state( init), (2, AppState { counter: Counter { hits: 2}, log: vec!["start".into(), "10".into()]}));
+    }
+
+    #[test]
+    fn test_tail_rec_m() {
+        const N: u32 = 1_000_000;
+        
+        let counter = tail_rec_m( 0, |n| get().bind( move |s: u64| put( s + n as u64).bind( move |_| {
+                         State::pure( if n < N {Step::Loop( n + 1)} else {Step::Done( n)})
+                      })));
+        assert_eq!( counter.initial_state( 0), (N, (N as u64) * (N as u64 + 1) / 2));
+    }
+
+    #[test]
+    fn test_traverse() {
+        let st = for_m_( 1..4, |x| get().bind( move |s: i32| put( s + x)));
+        assert_eq!( st.initial_state( 0), ((), 6));
+        
+        let st1: State<'_, i32, Vec<i32>> = traverse( |x| State::pure( x + 1), vec![1, 2]);
+        assert_eq!( st1.initial_state( 0), (vec![2, 3], 0));
+    }
+}
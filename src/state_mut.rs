@@ -0,0 +1,134 @@
+//! An in place State monad, with one-shot actions on a `&mut S`
+//!
+//! `State` threads the state by value and its `get()` clones it. `StateMut<'a, S, A>` is a boxed `FnOnce(&mut S) -> A`,
+//! so the actions update the state in place and neither the state nor the results need `Clone`.
+//! An action runs once, consumed by `run` or `initial_state`.
+//!
+//! ```
+//! # #[macro_use] extern crate monadic;
+//! use monadic::state_mut::{StateMut, modify, gets};
+//! use std::collections::HashMap;
+//!
+//! # fn main() {
+//! fn count<'a>(word: &'a str) -> StateMut<'a, HashMap<String, u32>, u32> {
+//!     stmdo!{
+//!         _ <- modify( move |m: &mut HashMap<String, u32>| *m.entry( word.to_string()).or_insert( 0) += 1);
+//!         gets( move |m: &HashMap<String, u32>| m[word])
+//!     }
+//! }
+//!
+//! let mut counts = HashMap::new();
+//! let n = stmdo!{
+//!     _ <- count( "a");
+//!     _ <- count( "b");
+//!     count( "a")
+//! }.run( &mut counts);
+//!
+//! assert_eq!( (n, counts.len()), (2, 2));
+//! # }
+//! ```
+
+pub struct StateMut<'a, S, A> {
+  run_state_mut: Box<dyn 'a + FnOnce(&mut S) -> A>,
+}
+
+impl<'a, S: 'a, A: 'a> StateMut<'a, S, A> {
+
+  /// an action of a function on the state
+  pub fn new<F>(f: F) -> Self
+    where F: 'a + FnOnce(&mut S) -> A
+  {
+    StateMut { run_state_mut: Box::new( f)}
+  }
+
+  pub fn pure(x: A) -> Self {
+    StateMut::new( move |_| x)
+  }
+
+  pub fn bind<B: 'a, F>(self, f: F) -> StateMut<'a, S, B>
+    where F: 'a + FnOnce(A) -> StateMut<'a, S, B>
+  {
+    StateMut::new( move |s: &mut S| {
+        let v = (self.run_state_mut) (s);
+        (f( v).run_state_mut) (s)
+    })
+  }
+
+  /// runs the action on the state in place
+  pub fn run(self, s: &mut S) -> A {
+    (self.run_state_mut) (s)
+  }
+
+  pub fn initial_state(self, mut s: S) -> (A, S) {
+    let a = (self.run_state_mut) (&mut s);
+    (a, s)
+  }
+}
+
+/// a clone of the state
+pub fn get<'a, S: 'a + Clone>() -> StateMut<'a, S, S> {
+   StateMut::new( |s: &mut S| s.clone())
+}
+
+pub fn put<'a, S: 'a>(s: S) -> StateMut<'a, S, ()> {
+   StateMut::new( move |st: &mut S| *st = s)
+}
+
+/// updates the state in place
+pub fn modify<'a, S: 'a, F>(f: F) -> StateMut<'a, S, ()>
+  where F: 'a + FnOnce(&mut S)
+{
+   StateMut::new( f)
+}
+
+/// a projection of the state
+pub fn gets<'a, S: 'a, A: 'a, F>(f: F) -> StateMut<'a, S, A>
+  where F: 'a + FnOnce(&S) -> A
+{
+   StateMut::new( move |s: &mut S| f( s))
+}
+
+/// macro for a `StateMut<'a, S, A>` monad with the syntax of `stdo`, whose steps may move values, as they run once
+#[macro_export]
+macro_rules! stmdo {
+  (pure $e:expr                           ) => [StateMut::pure($e)];
+  (let $v:ident = $e:expr ; $($rest:tt)*) => [StateMut::pure($e).bind( move |$v| { stmdo!($($rest)*)} )];
+  (_ <- $monad:expr ; $($rest:tt)* ) => [StateMut::bind(($monad), move |_| { stmdo!($($rest)*)} )];
+  ($v:ident <- pure $e:expr ; $($rest:tt)* ) => [StateMut::bind( StateMut::pure($e), move |$v| { stmdo!($($rest)*)} )];
+  ($v:ident <- $monad:expr ; $($rest:tt)* ) => [StateMut::bind(($monad), move |$v| { stmdo!($($rest)*)} )];
+  ($monad:expr                            ) => [$monad];
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state_mut::{StateMut, get, put, modify, gets};
+
+    // not Clone
+    struct Grid { cells: Vec<Vec<u8>> }
+
+    fn fill<'a>(row: usize, col: usize, v: u8) -> StateMut<'a, Grid, ()> {
+        modify( move |g: &mut Grid| g.cells[row][col] = v)
+    }
+
+    #[test]
+    fn test_state_mut() {
+        let name = String::from( "grid");
+        let block = stmdo!{
+            _ <- fill( 0, 1, 7);
+            _ <- fill( 1, 0, 3);
+            total <- gets( |g: &Grid| g.cells.iter().flatten().map( |&c| c as u32).sum::<u32>());
+            let label = name;  // moved, not cloned
+            pure format!( "{}: {}", label, total)
+        };
+        let mut grid = Grid { cells: vec![vec![0; 2]; 2]};
+        assert_eq!( block.run( &mut grid), "grid: 10");
+        assert_eq!( grid.cells, vec![vec![0, 7], vec![3, 0]]);
+        
+        let swap = stmdo!{
+            x <- get();
+            _ <- put( x * 2);
+            pure x
+        };
+        assert_eq!( swap.initial_state( 21), (21, 42));
+    }
+}
@@ -1,13 +1,27 @@
 // state_trans.rs 
 
-use crate::monad::Monad;
+use crate::monad::{self, Monad, Step};
+use crate::hkt::{Kind, Kinded, Functor, Applicative, MonadK};
 use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::rc::Rc;
 
-pub struct StateT<'a, S, M, A> 
-where
-  M: 'a + Monad<Item=(A, S)>,
-{ 
+/// The result type `A` is tracked by a marker field, instead of the former `M: Monad<Item=(A, S)>` struct bound,
+/// so that the inner monad may be any `Kind` constructor (module hkt) or a plain `IntoIterator` (`tail_rec_m`, `zoom`);
+/// build it with `StateT::new`.
+pub struct StateT<'a, S, M, A> { // M: Monad<Item=(A, S)>
   pub run_state_t: Box<dyn 'a + Fn(S) -> M>, 
+  _result: PhantomData<A>,
+}
+
+impl<'a, S, M, A> StateT<'a, S, M, A> {
+
+  /// a StateT of a `(s -> m (a, s))` function
+  pub fn new<F>(f: F) -> Self
+     where F: 'a + Fn(S) -> M
+  {
+    StateT { run_state_t: Box::new( f), _result: PhantomData}
+  }
 }
 
 impl<'a, A, S, M> StateT<'a, S, M, A> 
@@ -19,7 +33,7 @@ impl<'a, A, S, M> StateT<'a, S, M, A>
   /// This function requires type annotation of the inner monad
   pub fn pure(x: A) -> Self
   {
-    StateT { run_state_t: Box::new( move |s: S| M::pure(( x.clone(), s)))}  // (s -> return (a,s))
+    StateT::new( move |s: S| M::pure(( x.clone(), s)))  // (s -> return (a,s))
   }
   
   
@@ -27,22 +41,22 @@ impl<'a, A, S, M> StateT<'a, S, M, A>
     where
       N: 'a + Clone + Monad<Item=A>,
   {
-    StateT { run_state_t: Box::new( 
+    StateT::new( 
                         // pair each element with St
-                        move |s| n.clone().into_iter().map( | a| (a, s.clone()) 
+                        move |s: S| n.clone().into_iter().map( | a| (a, s.clone()) 
                                                           ).collect::<M>()
-                        )}
+                        )
   }
   
   pub fn lift_iter<I>(it: I) -> Self
     where
       I: 'a + Clone + Iterator<Item=A>,
   {
-    StateT { run_state_t: Box::new( 
+    StateT::new( 
                         // pair each element with St
-                        move |s| it.clone().map( | a| (a, s.clone()) 
+                        move |s: S| it.clone().map( | a| (a, s.clone()) 
                                                           ).collect::<M>()
-                        )}
+                        )
   }
   
   /// FromIterator is required to convert the inner monad bind output FlatMap struct to the Monad instance
@@ -52,11 +66,11 @@ impl<'a, A, S, M> StateT<'a, S, M, A>
        N: 'a + Monad<Item=(B, S)> + FromIterator<(B, S)>,
        B: 'a,
   {
-    StateT { run_state_t: Box::new( move |s: S| {
+    StateT::new( move |s: S| {
                   let m = (*self.run_state_t) (s); // the monad
                   let g = move |(v, s1)| (* f( v).run_state_t) (s1);
                   M::bind( m, g).collect::<N>()
-                  })}
+                  })
                  
    }
    
@@ -71,16 +85,56 @@ pub fn get<'a, S>() -> StateT<'a, S, Vec<(S, S)>, S>
   where
     S: 'a + Clone, 
 {
-   StateT { run_state_t: Box::new( |s: S| {let p = (s.clone(), s); Vec::pure(p)}
-                                 )} 
+   StateT::new( |s: S| {let p = (s.clone(), s); Vec::pure(p)}
+                                 ) 
 }
 
 pub fn put<'a, S>( s: S) -> StateT<'a, S, Vec<((), S)>, ()> 
   where
     S: 'a + Clone, 
 {
-   StateT { run_state_t: Box::new( move |_| {let p = ((), s.clone()); Vec::pure(p)} 
-                                 )} 
+   StateT::new( move |_| {let p = ((), s.clone()); Vec::pure(p)} 
+                                 ) 
+}
+
+/// stack safe loop over the inner monad results, running `f` on each `Loop` argument and state until it returns `Step::Done`;
+/// the results are collected in depth first order, as the ones of `bind`
+pub fn tail_rec_m<'a, S, M, N, A, B, F>(a: A, f: F) -> StateT<'a, S, N, B>
+  where
+    S: 'a,
+    A: 'a + Clone,
+    F: 'a + Fn(A) -> StateT<'a, S, M, Step<A, B>>,
+    M: 'a + IntoIterator<Item = (Step<A, B>, S)>,
+    N: 'a + FromIterator<(B, S)>,
+{
+   StateT::new( move |s: S| {
+                  let g = |(a, s)| (* f( a).run_state_t) (s).into_iter().map( |(step, s1)| match step {
+                                                                     Step::Loop(a1) => Step::Loop((a1, s1)),
+                                                                     Step::Done(b) => Step::Done((b, s1)),
+                                                                  });
+                  monad::tail_rec_m( (a.clone(), s), g).collect::<N>()
+                  })
+}
+
+/// runs a StateT on a part of the state, taken out by `getter` and put back by `setter` in a clone of the state for each result
+pub fn zoom<'a, S, T, M, N, A, G, H>(getter: G, setter: H, stt: StateT<'a, T, M, A>) -> StateT<'a, S, N, A>
+  where
+    S: 'a + Clone,
+    T: 'a,
+    A: 'a,
+    G: 'a + Fn(&mut S) -> T,
+    H: 'a + Fn(&mut S, T),
+    M: 'a + IntoIterator<Item = (A, T)>,
+    N: 'a + FromIterator<(A, S)>,
+{
+   StateT::new( move |mut s: S| {
+                  let t = getter( &mut s);
+                  (* stt.run_state_t) (t).into_iter().map( |(a, t1)| {
+                        let mut s1 = s.clone();
+                        setter( &mut s1, t1);
+                        (a, s1)
+                     }).collect::<N>()
+                  })
 }
 
 /* unused
@@ -91,14 +145,81 @@ pub fn lift<'a, S, A, M, N>(n: N) -> StateT<'a, S, M, A>
     N: 'a + Clone + Monad<Item=A>,
     M: 'a + Clone + Monad<Item=(A, S)> + FromIterator<(A, S)>, 
 {
-    StateT { run_state_t: Box::new( 
+    StateT::new( 
                         // pair each element with St
-                        move |s| n.clone().into_iter().map( | a| (a, s.clone()) 
+                        move |s: S| n.clone().into_iter().map( | a| (a, s.clone()) 
                                                           ).collect::<M>()
-                        )}
+                        )
 }
 */
 
+//--------------------------------------------
+
+/// type constructor marker of `StateT<'a, S, MK::Of<(_, S)>, _>` over the inner monad marker `MK`, for the traits in module hkt
+pub struct StateTK<S, MK>(PhantomData<(S, MK)>);
+
+impl<'a, S: 'a, MK: Kind<'a>> Kind<'a> for StateTK<S, MK> {
+    type Of<A: 'a> = StateT<'a, S, MK::Of<(A, S)>, A>;
+}
+
+impl<'a, S: 'a, A: 'a, M: 'a + Kinded<'a, Elem = (A, S)>> Kinded<'a> for StateT<'a, S, M, A> {
+    type K = StateTK<S, M::K>;
+    type Elem = A;
+
+    fn into_kind(self) -> StateT<'a, S, <M::K as Kind<'a>>::Of<(A, S)>, A> {
+       StateT::new( move |s: S| (* self.run_state_t)( s).into_kind())
+    }
+}
+
+impl<'a, S: 'a, MK: 'a + Functor<'a>> Functor<'a> for StateTK<S, MK> {
+
+    fn fmap<A: 'a, B: 'a, F>(fa: StateT<'a, S, MK::Of<(A, S)>, A>, f: F) -> StateT<'a, S, MK::Of<(B, S)>, B>
+       where F: 'a + Fn(A) -> B
+    {
+       let f = Rc::new( f);
+       StateT::new( move |s: S| {
+                  let f = f.clone();
+                  MK::fmap( (* fa.run_state_t)( s), move |(a, s1)| (f( a), s1))
+                  })
+    }
+}
+
+impl<'a, S: 'a + Clone, MK: 'a + MonadK<'a>> Applicative<'a> for StateTK<S, MK> {
+
+    fn of<A: 'a + Clone>(x: A) -> StateT<'a, S, MK::Of<(A, S)>, A> {
+       StateT::new( move |s: S| MK::of(( x.clone(), s)))
+    }
+
+    /// runs `fa` before `fb`, threading the state through each inner monad result
+    fn lift_a2<A: 'a + Clone, B: 'a + Clone, C: 'a, F>(fa: StateT<'a, S, MK::Of<(A, S)>, A>, fb: StateT<'a, S, MK::Of<(B, S)>, B>, f: F) 
+          -> StateT<'a, S, MK::Of<(C, S)>, C>
+       where F: 'a + Fn(A, B) -> C
+    {
+       let fb = Rc::new( fb);
+       let f = Rc::new( f);
+       StateT::new( move |s: S| {
+                  let (fb, f) = (fb.clone(), f.clone());
+                  MK::flat_map( (* fa.run_state_t)( s), move |(a, s1)| {
+                      let f = f.clone();
+                      MK::fmap( (* fb.run_state_t)( s1), move |(b, s2)| (f( a.clone(), b), s2))
+                  })
+                  })
+    }
+}
+
+impl<'a, S: 'a + Clone, MK: 'a + MonadK<'a>> MonadK<'a> for StateTK<S, MK> {
+
+    fn flat_map<A: 'a + Clone, B: 'a, F>(fa: StateT<'a, S, MK::Of<(A, S)>, A>, f: F) -> StateT<'a, S, MK::Of<(B, S)>, B>
+       where F: 'a + Fn(A) -> StateT<'a, S, MK::Of<(B, S)>, B>
+    {
+       let f = Rc::new( f);
+       StateT::new( move |s: S| {
+                  let f = f.clone();
+                  MK::flat_map( (* fa.run_state_t)( s), move |(a, s1)| (* f( a).run_state_t)( s1))
+                  })
+    }
+}
+
 /// macro for a `StateT<'a, S, M, A>` monad transformer with a boxed `(a -> m (a, s)) where M: Monad`;
 /// It uses the type alias St in type annotations.
 #[macro_export]
@@ -124,3 +245,36 @@ macro_rules! stt_mdo {
   ($monad:expr                            ) => [$monad];
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::state_trans::{StateT, get, put, tail_rec_m};
+    use crate::monad::Step;
+
+    // the macro zoom_field is defined in module state
+    #[cfg(feature="state")]
+    #[test]
+    fn test_zoom() {
+        use crate::{zoom_field, state_trans::zoom};
+        
+        #[derive(Clone, Debug, PartialEq)]
+        struct Board { pos: i32, moves: u32 }
+        
+        // each move goes one step back or forward
+        let step: StateT<'_, i32, Vec<((), i32)>, ()> = get().bind( |p: i32| StateT::<'_, i32, Vec<_>, _>::lift( vec![p - 1, p + 1]).bind( put));
+        
+        let zoomed: StateT<'_, Board, Vec<((), Board)>, ()> = zoom_field!( Board, pos, step);
+        let boards = zoomed.initial_state( Board { pos: 0, moves: 3}).into_iter().map( |(_, b)| b).collect::<Vec<_>>();
+        assert_eq!( boards, vec![Board { pos: -1, moves: 3}, Board { pos: 1, moves: 3}]);
+    }
+
+    #[test]
+    fn test_tail_rec_m() {
+        const N: u32 = 1_000_000;
+        
+        let stt: StateT<'_, u32, Vec<(u32, u32)>, u32> = tail_rec_m( 0, |n| get().bind( move |s| {
+                     if n < N {put( s + 1).bind( move |_| StateT::<'_, u32, Vec<_>, _>::pure( Step::Loop( n + 1)))} 
+                     else {StateT::lift( vec![Step::Done( n), Step::Done( 0)])}
+                  }));
+        assert_eq!( stt.initial_state( 0), vec![(N, N), (0, N)]);
+    }
+}
@@ -0,0 +1,336 @@
+//! A memoizing lazy stream monad
+//!
+//! Re-iterating a `mdo` result runs every closure again, and one-shot iterators can't be traversed twice.
+//! `Stream<'a, T>` is a list of memoized cons cells: a cell is forced from the source iterator
+//! the first time a consumer reaches it, and shared afterwards, so clones of a stream see every element
+//! computed at most once. Traversals clone the shared elements, so they require `T: Clone`.
+//!
+//! Its inherent `bind` takes precedence over `Bind::bind`, so a `mdo` block whose first source is a `Stream`
+//! produces a memoized `Stream`.
+//!
+//! ```
+//! # #[macro_use] extern crate monadic;
+//! use monadic::{mdo, monad::{Bind, Monad}, stream::Stream};
+//! use std::cell::Cell;
+//!
+//! # fn main() {
+//! let counter = Cell::new( 0);
+//! let steps = &counter;
+//! let squares = mdo!{
+//!     x <- Stream::new( 1..=3);
+//!     let _ = steps.set( steps.get() + 1);
+//!     pure x * x
+//! };
+//! let shared = squares.clone();
+//!
+//! assert_eq!( squares.into_iter().collect::<Vec<_>>(), vec![1, 4, 9]);
+//! assert_eq!( shared.into_iter().collect::<Vec<_>>(), vec![1, 4, 9]);
+//! assert_eq!( counter.get(), 3);
+//! # }
+//! ```
+
+use crate::monad::{Monad, MZero, MPlus};
+use std::cell::RefCell;
+use std::iter::{self, FromIterator};
+use std::mem;
+use std::rc::Rc;
+
+pub struct Stream<'a, T> {
+  node: Rc<RefCell<Node<'a, T>>>,
+}
+
+enum Node<'a, T> {
+  Delayed(Box<dyn 'a + Iterator<Item = T>>),
+  /// a cell whose source is advancing
+  Forcing,
+  Cons(T, Stream<'a, T>),
+  Nil,
+}
+
+impl<'a, T> Clone for Stream<'a, T> {
+  fn clone(&self) -> Self {
+     Stream { node: self.node.clone()}
+  }
+}
+
+/// unlinks the unshared forced cells in a loop, as the default recursive drop of the tails overflows the stack on long streams
+impl<'a, T> Drop for Stream<'a, T> {
+  fn drop(&mut self) {
+     let mut next = take_tail( &mut self.node);
+     while let Some(mut stream) = next {
+        next = take_tail( &mut stream.node);
+     }
+  }
+}
+
+/// the tail of a forced cell that no other stream shares, leaving it empty
+fn take_tail<'a, T>(node: &mut Rc<RefCell<Node<'a, T>>>) -> Option<Stream<'a, T>> {
+  let cell = Rc::get_mut( node)?.get_mut();
+  if let Node::Cons(..) = cell {
+     if let Node::Cons( _, tail) = mem::replace( cell, Node::Nil) {
+        return Some(tail);
+     }
+  }
+  None
+}
+
+impl<'a, T: 'a> Stream<'a, T> {
+
+  /// a lazy stream over the iterable, pulling each element once
+  pub fn new<I>(xs: I) -> Self
+    where I: 'a + IntoIterator<Item = T>,
+  {
+     Stream::from_node( Node::Delayed( Box::new( xs.into_iter())))
+  }
+
+  pub fn nil() -> Self {
+     Stream::from_node( Node::Nil)
+  }
+
+  pub fn cons(x: T, tail: Stream<'a, T>) -> Self {
+     Stream::from_node( Node::Cons( x, tail))
+  }
+
+  pub fn pure(x: T) -> Self {
+     Stream::cons( x, Stream::nil())
+  }
+
+  pub fn mzero() -> Self {
+     Stream::nil()
+  }
+
+  fn from_node(node: Node<'a, T>) -> Self {
+     Stream { node: Rc::new( RefCell::new( node))}
+  }
+
+  /// evaluates the first cell, if still delayed, memoizing it
+  ///
+  /// The cell is marked as being forced while its source advances: reading it meanwhile, as a source reading
+  /// its own stream does, panics, and a panic of the source puts it back in the cell.
+  fn force(&self) {
+     let node = mem::replace( &mut *self.node.borrow_mut(), Node::Forcing);
+     let source = match node {
+        Node::Delayed(source) => source,
+        Node::Forcing => panic!( "Stream: a cell was read while its source was evaluating it"),
+        node => {
+           *self.node.borrow_mut() = node;
+           return;
+        },
+     };
+     let mut guard = Restore { node: &self.node, source: Some(source)};
+     let next = guard.source.as_mut().and_then( |source| source.next());
+     let source = guard.source.take().expect( "Stream: the source was already restored");
+     *self.node.borrow_mut() = match next {
+        Some(x) => Node::Cons( x, Stream::from_node( Node::Delayed( source))),
+        None => Node::Nil,
+     };
+  }
+
+  pub fn is_empty(&self) -> bool {
+     self.force();
+     matches!( *self.node.borrow(), Node::Nil)
+  }
+
+  /// the first element and the rest, forcing the first cell
+  pub fn uncons(&self) -> Option<(T, Stream<'a, T>)>
+    where T: Clone
+  {
+     self.force();
+     match &*self.node.borrow() {
+        Node::Cons( x, tail) => Some((x.clone(), tail.clone())),
+        _ => None,
+     }
+  }
+
+  pub fn head(&self) -> Option<T>
+    where T: Clone
+  {
+     self.uncons().map( |(x, _)| x)
+  }
+
+  pub fn tail(&self) -> Option<Stream<'a, T>>
+    where T: Clone
+  {
+     self.uncons().map( |(_, tail)| tail)
+  }
+
+  /// a traversal of the stream, forcing the cells as it goes
+  pub fn iter(&self) -> StreamIter<'a, T> {
+     StreamIter { cur: self.clone()}
+  }
+
+  /// lazy and memoized bind, where the continuation may return any IntoIterator, as the ones of `mdo`
+  pub fn bind<U, F>(self, f: F) -> Stream<'a, U::Item>
+     where
+       F: 'a + Fn(T) -> U,
+       U: 'a + IntoIterator,
+       T: Clone,
+  {
+     Stream::new( self.iter().flat_map( f))
+  }
+
+  /// concatenation, memoized
+  pub fn mplus(self, other: Self) -> Self
+    where T: Clone
+  {
+     Stream::new( self.iter().chain( other.iter()))
+  }
+}
+
+/// puts the source back in the cell being forced when the source panics
+struct Restore<'s, 'a, T> {
+  node: &'s RefCell<Node<'a, T>>,
+  source: Option<Box<dyn 'a + Iterator<Item = T>>>,
+}
+
+impl<'s, 'a, T> Drop for Restore<'s, 'a, T> {
+  fn drop(&mut self) {
+     if let Some(source) = self.source.take() {
+        *self.node.borrow_mut() = Node::Delayed( source);
+     }
+  }
+}
+
+/// iterator over the shared cells of a stream
+pub struct StreamIter<'a, T> {
+  cur: Stream<'a, T>,
+}
+
+impl<'a, T: 'a + Clone> Iterator for StreamIter<'a, T> {
+  type Item = T;
+
+  fn next(&mut self) -> Option<T> {
+     let (x, tail) = self.cur.uncons()?;
+     self.cur = tail;
+     Some(x)
+  }
+}
+
+impl<'a, T: 'a + Clone> IntoIterator for Stream<'a, T> {
+  type Item = T;
+  type IntoIter = StreamIter<'a, T>;
+
+  fn into_iter(self) -> StreamIter<'a, T> {
+     StreamIter { cur: self}
+  }
+}
+
+impl<'a, T: 'a + Clone> IntoIterator for &Stream<'a, T> {
+  type Item = T;
+  type IntoIter = StreamIter<'a, T>;
+
+  fn into_iter(self) -> StreamIter<'a, T> {
+     self.iter()
+  }
+}
+
+/// a strict stream of the collected elements
+impl<'a, T: 'a> FromIterator<T> for Stream<'a, T> {
+  fn from_iter<I: IntoIterator<Item = T>>(xs: I) -> Self {
+     Stream::new( xs.into_iter().collect::<Vec<T>>())
+  }
+}
+
+impl<'a, T: 'a + Clone> Monad for Stream<'a, T> {
+  fn pure(x: T) -> Self {
+     Stream::pure( x)
+  }
+}
+
+impl<'a, T: 'a + Clone> MZero for Stream<'a, T> {
+  fn mzero() -> Self {
+     Stream::nil()
+  }
+}
+
+impl<'a, T: 'a + Clone> MPlus for Stream<'a, T> {
+  fn mplus(&mut self, other: &mut Self) {
+     *self = self.clone().mplus( other.clone());
+  }
+}
+
+/// the lines of the standard input as a stream, ending at the first read error or end of input
+pub fn stdin_lines<'a>() -> Stream<'a, String> {
+  Stream::new( iter::from_fn( || match crate::mio::read_line() {
+                                    Ok(line) if !line.is_empty() => Some(line),
+                                    _ => None,
+                                  }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mdo;
+    use crate::stream::Stream;
+    use crate::monad::{Monad, MPlus};
+    use std::cell::Cell;
+
+    #[test]
+    fn test_memoized_stream() {
+        let pulls = Cell::new( 0);
+        let source = Stream::new( (1..).inspect( |_| pulls.set( pulls.get() + 1)));
+
+        assert_eq!( source.iter().take( 3).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!( source.iter().take( 2).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!( pulls.get(), 3);
+
+        let (head, tail) = source.uncons().unwrap();
+        assert_eq!( (head, tail.head()), (1, Some(2)));
+
+        let one_shot = vec![String::from( "a"), String::from( "b")].into_iter();
+        let words = Stream::new( one_shot);
+        let pairs = mdo!{
+            w <- words.clone();
+            v <- words.clone();
+            pure format!( "{}{}", w, v)
+        };
+        assert_eq!( pairs.iter().collect::<Vec<_>>(), vec!["aa", "ab", "ba", "bb"]);
+        assert_eq!( pairs.iter().count(), 4);
+
+        let mut xs = Stream::new( 1..3);
+        MPlus::mplus( &mut xs, &mut Stream::pure( 9));
+        assert_eq!( xs.into_iter().collect::<Vec<_>>(), vec![1, 2, 9]);
+        assert!( Stream::<i32>::nil().is_empty());
+    }
+
+    #[test]
+    fn test_long_stream_drop() {
+        let xs = Stream::new( 0..1_000_000);
+        assert_eq!( xs.iter().count(), 1_000_000);
+        drop( xs);
+        
+        let mut ys = Stream::nil();
+        for i in 0..1_000_000 {
+            ys = Stream::cons( i, ys);
+        }
+        assert_eq!( ys.head(), Some(999_999));
+    }
+
+    #[test]
+    fn test_source_reading_its_stream() {
+        use std::rc::Rc;
+        use std::cell::RefCell;
+        use std::panic::{self, AssertUnwindSafe};
+        
+        // the source reading its own stream panics instead of seeing it truncated
+        let this: Rc<RefCell<Option<Stream<'_, usize>>>> = Rc::new( RefCell::new( None));
+        let reader = this.clone();
+        let xs = Stream::new( (0..3).map( move |_| reader.borrow().as_ref().map_or( 0, |s| s.iter().count())));
+        *this.borrow_mut() = Some( xs.clone());
+        let res = panic::catch_unwind( AssertUnwindSafe( || xs.iter().count()));
+        let msg = res.expect_err( "the reentrant read should panic");
+        assert_eq!( msg.downcast_ref::<&str>(), Some(&"Stream: a cell was read while its source was evaluating it"));
+        this.borrow_mut().take();
+    }
+
+    #[test]
+    fn test_panicking_source() {
+        use std::cell::Cell;
+        use std::panic::{self, AssertUnwindSafe};
+        
+        // the source panics once on its second element, and is put back in the cell
+        let failed = Cell::new( false);
+        let xs = Stream::new( (1..=3).inspect( |&x| if x == 2 && !failed.replace( true) { panic!( "source failure")}));
+        assert!( panic::catch_unwind( AssertUnwindSafe( || xs.iter().count())).is_err());
+        assert_eq!( xs.iter().collect::<Vec<_>>(), vec![1, 3]);
+    }
+}
@@ -1,6 +1,9 @@
 //! A Writer monad implementation
 
 use crate::monoid::{Monoid};
+use crate::monad::Step;
+use crate::hkt::{Kind, Kinded, Functor, Applicative, MonadK};
+use std::marker::PhantomData;
 
 //---------------------------------------------
 
@@ -124,6 +127,71 @@ pub fn listens<A, W: Clone, T, F: Fn(W) -> T>( f: F, writer: Writer<A, W>) -> Wr
     Writer{ run_writer: ((a, f( (&w).clone())), w)}
 }
 
+/// stack safe loop, running `f` on the argument and appending the logs until it returns `Step::Done`
+pub fn tail_rec_m<A, B, W: Monoid, F>(a: A, f: F) -> Writer<B, W>
+    where F: Fn(A) -> Writer<Step<A, B>, W>
+{
+    let (mut a, mut w) = (a, W::mempty());
+    loop {
+        let (step, mut w1) = f( a).run_writer;
+        w = w.mappend( &mut w1);
+        match step {
+            Step::Loop(a1) => a = a1,
+            Step::Done(b) => return Writer{ run_writer: (b, w)},
+        }
+    }
+}
+
+//---------------------------------------------
+
+/// type constructor marker of `Writer<_, W>` for the traits in module hkt
+pub struct WriterK<W>(PhantomData<W>);
+
+impl<'a, W> Kind<'a> for WriterK<W> {
+    type Of<A: 'a> = Writer<A, W>;
+}
+
+impl<'a, A: 'a, W> Kinded<'a> for Writer<A, W> {
+    type K = WriterK<W>;
+    type Elem = A;
+
+    fn into_kind(self) -> Writer<A, W> { self }
+}
+
+impl<'a, W> Functor<'a> for WriterK<W> {
+
+    fn fmap<A: 'a, B: 'a, F>(fa: Writer<A, W>, f: F) -> Writer<B, W>
+       where F: 'a + Fn(A) -> B
+    {
+       let (a, w) = fa.run_writer;
+       Writer{ run_writer: (f( a), w)}
+    }
+}
+
+impl<'a, W: Monoid + Clone> Applicative<'a> for WriterK<W> {
+
+    fn of<A: 'a + Clone>(x: A) -> Writer<A, W> {
+       Writer::pure( x)
+    }
+
+    /// appends the log of `fb` to the log of `fa`
+    fn lift_a2<A: 'a + Clone, B: 'a + Clone, C: 'a, F>(fa: Writer<A, W>, fb: Writer<B, W>, f: F) -> Writer<C, W>
+       where F: 'a + Fn(A, B) -> C
+    {
+       let (a, w) = fa.run_writer;
+       let (b, mut w1) = fb.run_writer;
+       Writer{ run_writer: (f( a, b), w.mappend( &mut w1))}
+    }
+}
+
+impl<'a, W: Monoid + Clone> MonadK<'a> for WriterK<W> {
+
+    fn flat_map<A: 'a + Clone, B: 'a, F>(fa: Writer<A, W>, f: F) -> Writer<B, W>
+       where F: 'a + Fn(A) -> Writer<B, W>
+    {
+       fa.bind( f)
+    }
+}
     
 /// macro for a `Writer<A, W = String>` monad that holds a pair (A, Monoid)
 ///
@@ -138,3 +206,22 @@ macro_rules! wrdo {
   ($v:ident <- $monad:expr ; $($rest:tt)* ) => [Writer::bind( ($monad), move |$v| { wrdo!($($rest)*)} )];
   ($monad:expr                            ) => [$monad];
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::{Writer, tell, tail_rec_m};
+    use crate::monad::Step;
+    use crate::control::sequence_;
+
+    #[test]
+    fn test_tail_rec_m() {
+        let wr = tail_rec_m( 0, |n| tell( vec![n]).bind( move |_| Writer::pure( if n < 3 {Step::Loop( n + 1)} else {Step::Done( n)})));
+        assert_eq!( wr.unwrap_pair(), (3, vec![0, 1, 2, 3]));
+    }
+
+    #[test]
+    fn test_traverse() {
+        let wr = sequence_( (1..4).map( |x| tell( vec![x])));
+        assert_eq!( wr.unwrap_pair(), ((), vec![1, 2, 3]));
+    }
+}
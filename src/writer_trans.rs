@@ -1,10 +1,12 @@
 // writer_trans.rs 
 
 #[allow(unused_imports)]
-use crate::monad::{Bind, Monad, MZero, MPlus};
+use crate::monad::{Bind, Monad, MZero, MPlus, Step};
 use crate::monoid::Monoid;
+use crate::hkt::{Kind, Kinded, Functor, Applicative, MonadK, ListKind};
 use std::iter::FromIterator;
 use std::collections::LinkedList;
+use std::marker::PhantomData;
 
 
 #[derive(Clone)]  
@@ -146,7 +148,111 @@ pub fn tell_str(s: &str) -> WriterT<Vec<()>, String> {
 pub fn tell_array<T: Clone>(v: &[T]) -> WriterT<Vec<()>, Vec<T>> {
         WriterT{ run_writer_t: (vec!(()), Vec::from( v))}
     }
+
+/// stack safe loop over the inner monad results, running `f` on each `Loop` argument until it returns `Step::Done`;
+///
+/// As in `bind`, the results are concatenated in depth first order, and the log is the one of the path of first results.
+///
+/// It is strict: the whole loop, with every branch, runs when called, as the WriterT pair holds the collected results;
+/// an endless loop never returns, unlike the lazy `monad::tail_rec_m`. The log is appended step by step, as in `writer::tail_rec_m`.
+pub fn tail_rec_m<A, B, M, N, W, F>(a: A, f: F) -> WriterT<N, W> 
+  where
+    F: Fn(A) -> WriterT<M, W>,
+    M: IntoIterator<Item = Step<A, B>>,
+    N: FromIterator<B>,
+    W: Monoid,
+{
+   let (m, mut w_out) = f( a).run_writer_t;
+   // pending branches, flagged when on the path of first results
+   let mut stack = vec![(m.into_iter().peekable(), true)];
+   let mut out = Vec::new();
+   
+   while let Some((mut branch, first)) = stack.pop() {
+      if let Some(step) = branch.next() {
+         if branch.peek().is_some() {
+            stack.push( (branch, false));
+         }
+         match step {
+            Step::Loop(a1) => {
+               let (m1, mut w1) = f( a1).run_writer_t;
+               if first {
+                  w_out = w_out.mappend( &mut w1);
+               }
+               stack.push( (m1.into_iter().peekable(), first));
+            },
+            Step::Done(b) => out.push( b),
+         }
+      }
+   }
+   WriterT { run_writer_t: (out.into_iter().collect::<N>(), w_out)}
+}
     
+//--------------------------------------------
+
+/// type constructor marker of `WriterT<MK::Of<_>, W>` over the inner collection marker `MK`, for the traits in module hkt
+pub struct WriterTK<W, MK>(PhantomData<(W, MK)>);
+
+impl<'a, W, MK: Kind<'a>> Kind<'a> for WriterTK<W, MK> {
+    type Of<A: 'a> = WriterT<MK::Of<A>, W>;
+}
+
+impl<'a, M: Kinded<'a>, W> Kinded<'a> for WriterT<M, W> {
+    type K = WriterTK<W, M::K>;
+    type Elem = M::Elem;
+
+    fn into_kind(self) -> WriterT<<M::K as Kind<'a>>::Of<M::Elem>, W> {
+       let (m, w) = self.run_writer_t;
+       WriterT { run_writer_t: (m.into_kind(), w)}
+    }
+}
+
+impl<'a, W, MK: Functor<'a>> Functor<'a> for WriterTK<W, MK> {
+
+    fn fmap<A: 'a, B: 'a, F>(fa: WriterT<MK::Of<A>, W>, f: F) -> WriterT<MK::Of<B>, W>
+       where F: 'a + Fn(A) -> B
+    {
+       let (m, w) = fa.run_writer_t;
+       WriterT { run_writer_t: (MK::fmap( m, f), w)}
+    }
+}
+
+impl<'a, W: Monoid, MK: ListKind<'a>> Applicative<'a> for WriterTK<W, MK> {
+
+    fn of<A: 'a + Clone>(x: A) -> WriterT<MK::Of<A>, W> {
+       WriterT { run_writer_t: (MK::of( x), W::mempty())}
+    }
+
+    /// as in `bind`, the log of `fb` is appended only if `fa` is not empty
+    fn lift_a2<A: 'a + Clone, B: 'a + Clone, C: 'a, F>(fa: WriterT<MK::Of<A>, W>, fb: WriterT<MK::Of<B>, W>, f: F) -> WriterT<MK::Of<C>, W>
+       where F: 'a + Fn(A, B) -> C
+    {
+       let (m, w) = fa.run_writer_t;
+       let (n, mut w1) = fb.run_writer_t;
+       let xs = MK::to_vec( m);
+       let w_out = if xs.is_empty() {w} else {w.mappend( &mut w1)};
+       WriterT { run_writer_t: (MK::lift_a2( MK::from_vec( xs), n, f), w_out)}
+    }
+}
+
+impl<'a, W: Monoid, MK: ListKind<'a>> MonadK<'a> for WriterTK<W, MK> {
+
+    /// as in `bind`, the results of each branch are concatenated and the log of the first branch is appended
+    fn flat_map<A: 'a + Clone, B: 'a, F>(fa: WriterT<MK::Of<A>, W>, f: F) -> WriterT<MK::Of<B>, W>
+       where F: 'a + Fn(A) -> WriterT<MK::Of<B>, W>
+    {
+       let (m, mut w_out) = fa.run_writer_t;
+       let mut out = Vec::new();
+       for (i, a) in MK::to_vec( m).into_iter().enumerate() {
+           let (n, mut w1) = f( a).run_writer_t;
+           if i == 0 {
+              w_out = w_out.mappend( &mut w1);
+           }
+           out.append( &mut MK::to_vec( n));
+       }
+       WriterT { run_writer_t: (MK::from_vec( out), w_out)}
+    }
+}
+
 /// macro for a `WriterT<M, W = String>` monad transformer holding a pair `(M, W) where M: MPlus + FromIterator, W: Monoid;`
 /// It uses the type alias Log in type annotations
 #[macro_export]
@@ -184,3 +290,19 @@ macro_rules! wrt_mdo {
   ($monad:expr                            ) => [$monad];
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::writer_trans::{WriterT, tail_rec_m};
+    use crate::monad::Step;
+
+    #[test]
+    fn test_tail_rec_m() {
+        // the words of length 2 over "ab", and the log of the first path
+        let wrt: WriterT<Vec<String>, Vec<usize>> = tail_rec_m( String::new(), |w: String| {
+                     let len = w.len();
+                     let steps = if len == 2 {vec![Step::Done( w)]} else {vec![Step::Loop( w.clone() + "a"), Step::Loop( w + "b")]};
+                     WriterT::lift( steps).censor( move |_| vec![len])
+                  });
+        assert_eq!( wrt.unwrap_pair(), (vec!["aa".to_string(), "ab".into(), "ba".into(), "bb".into()], vec![0, 1, 2]));
+    }
+}